@@ -0,0 +1,42 @@
+//! throughput of `HashRing::get` per hasher
+//!
+//! run all hashers with `cargo bench --all-features`
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use hashring_coordinator::HashRing;
+use std::hash::BuildHasher;
+use std::hint::black_box;
+
+fn nodes() -> Vec<String> {
+    (1..=10).map(|i| format!("127.0.0.{i}")).collect()
+}
+
+fn bench_ring<S: BuildHasher>(c: &mut Criterion, name: &str, mut ring: HashRing<String, S>) {
+    ring.batch_add(nodes());
+
+    let keys: Vec<String> = (0..1000).map(|i| format!("key_{i}")).collect();
+
+    c.bench_function(&format!("get {name}"), |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(ring.get(black_box(key)));
+            }
+        })
+    });
+}
+
+fn bench_get(c: &mut Criterion) {
+    bench_ring(c, "siphash", HashRing::new(2, 100));
+
+    #[cfg(feature = "xxhash")]
+    bench_ring(c, "xxh3", HashRing::new_xxh3(2, 100));
+
+    #[cfg(feature = "murmur3")]
+    bench_ring(c, "murmur3", HashRing::new_murmur3(2, 100));
+
+    #[cfg(feature = "fnv")]
+    bench_ring(c, "fnv", HashRing::new_fnv(2, 100));
+}
+
+criterion_group!(benches, bench_get);
+criterion_main!(benches);
@@ -0,0 +1,820 @@
+//! SWIM-style gossip membership for a ring of peers without an external coordinator
+//!
+//! Each peer probes one member per tick, asks other members to probe indirectly if no ack arrives, suspects
+//! members that do not answer and declares them dead after a while. Membership changes are piggybacked on probe
+//! messages. The converged view of each peer drives its own `HashRing` (via `Membership`), thus every peer computes
+//! the same ring and replication plans locally.
+
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use crate::hashring::strategy::{ReplicationStrategy, SimpleStrategy, splitmix64};
+use crate::hashring::{DefaultHashBuilder, HashRing};
+use crate::replication::membership::{Membership, MembershipEvent};
+
+/// state of a member as seen by a peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub enum MemberState {
+    Alive,
+    /// the member did not answer a probe, it is still part of the HashRing until it is declared dead
+    Suspect,
+    /// the member is removed from the HashRing
+    Dead,
+}
+
+/// member of the cluster, also used to disseminate membership changes
+///
+/// The incarnation is increased by the member itself only, to refute that it is suspected or dead.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Member<T> {
+    pub node: T,
+    pub state: MemberState,
+    pub incarnation: u64,
+}
+
+/// message sent between peers, each message carries membership changes (`updates`)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub enum Message<T> {
+    /// direct probe, answered with an `Ack`
+    Ping {
+        from: T,
+        seq: u64,
+        updates: Vec<Member<T>>,
+    },
+    /// ask another member to probe `target`, its `Ack` is forwarded
+    PingReq {
+        from: T,
+        target: T,
+        seq: u64,
+        updates: Vec<Member<T>>,
+    },
+    /// answer to a `Ping` of `seq`, `from` is the probed member (also if forwarded)
+    Ack {
+        from: T,
+        seq: u64,
+        updates: Vec<Member<T>>,
+    },
+}
+
+/// Transport delivers messages between peers, messages may be lost
+pub trait Transport<T> {
+    /// send `message` to `to`, without waiting for delivery
+    fn send(&mut self, to: &T, message: Message<T>);
+
+    /// returns all messages received since the last call
+    fn receive(&mut self) -> Vec<Message<T>>;
+}
+
+/// in-process network connecting `ChannelTransport`s, e.g. to test gossip within one process
+///
+/// Peers can be marked as down to simulate crashes or network partitions.
+#[derive(Clone, Debug)]
+pub struct ChannelNetwork<T> {
+    peers: Arc<Mutex<Vec<Inbox<T>>>>,
+}
+
+#[derive(Debug)]
+struct Inbox<T> {
+    node: T,
+    sender: Sender<Message<T>>,
+    down: bool,
+}
+
+impl<T> Default for ChannelNetwork<T> {
+    fn default() -> Self {
+        ChannelNetwork {
+            peers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> ChannelNetwork<T> {
+    /// Create a new, empty network
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns the transport for `node`, messages to `node` are delivered to this transport
+    pub fn connect(&self, node: T) -> ChannelTransport<T> {
+        let (sender, receiver) = channel();
+
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers.retain(|inbox| inbox.node != node);
+        peers.push(Inbox {
+            node: node.clone(),
+            sender,
+            down: false,
+        });
+
+        ChannelTransport {
+            node,
+            network: self.clone(),
+            receiver,
+        }
+    }
+
+    /// a node that is down neither sends nor receives messages
+    pub fn set_down(&self, node: &T, down: bool) {
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(inbox) = peers.iter_mut().find(|inbox| inbox.node == *node) {
+            inbox.down = down;
+        }
+    }
+
+    fn is_down(&self, node: &T) -> bool {
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+
+        peers
+            .iter()
+            .find(|inbox| inbox.node == *node)
+            .is_none_or(|inbox| inbox.down)
+    }
+}
+
+/// Transport of a single node within a `ChannelNetwork`
+#[derive(Debug)]
+pub struct ChannelTransport<T> {
+    node: T,
+    network: ChannelNetwork<T>,
+    receiver: Receiver<Message<T>>,
+}
+
+impl<T: Clone + PartialEq> Transport<T> for ChannelTransport<T> {
+    fn send(&mut self, to: &T, message: Message<T>) {
+        if self.network.is_down(&self.node) || self.network.is_down(to) {
+            return;
+        }
+
+        let peers = self.network.peers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(inbox) = peers.iter().find(|inbox| inbox.node == *to) {
+            let _ = inbox.sender.send(message);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<Message<T>> {
+        let messages: Vec<Message<T>> = self.receiver.try_iter().collect();
+
+        match self.network.is_down(&self.node) {
+            true => vec![],
+            false => messages,
+        }
+    }
+}
+
+/// timeouts (in ticks) and fan-out of the gossip protocol
+#[derive(Clone, Debug, PartialEq)]
+pub struct GossipConfig {
+    /// ticks to wait for an ack before other members are asked to probe (indirect probe)
+    pub ack_timeout: u64,
+    /// ticks to wait for any ack before the probed member is suspected
+    pub probe_timeout: u64,
+    /// ticks a member stays suspected before it is declared dead
+    pub suspicion_timeout: u64,
+    /// number of members asked to probe indirectly
+    pub indirect_probes: usize,
+    /// number of messages each membership change is piggybacked on
+    pub retransmits: usize,
+    /// seed of the random number generator used to select members (mixed with the hash of the peer)
+    pub seed: u64,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        GossipConfig {
+            ack_timeout: 3,
+            probe_timeout: 9,
+            suspicion_timeout: 12,
+            indirect_probes: 3,
+            retransmits: 10,
+            seed: 0,
+        }
+    }
+}
+
+// a member and the tick its state changed
+struct Entry<T> {
+    member: Member<T>,
+    since: u64,
+}
+
+struct Probe<T> {
+    target: T,
+    seq: u64,
+    started: u64,
+    indirect: bool,
+}
+
+// a probe requested by `origin` via PingReq
+struct Relay<T> {
+    target: T,
+    seq: u64,
+    origin: T,
+    since: u64,
+}
+
+/// Gossip runs the SWIM protocol for a single peer and keeps its HashRing in line with the members it sees
+///
+/// The protocol is driven by calling `tick` periodically (e.g. every 200ms), no threads are spawned.
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::HashRing;
+/// use hashring_coordinator::gossip::{ChannelNetwork, Gossip, GossipConfig};
+///
+/// let network = ChannelNetwork::new();
+///
+/// let mut peers: Vec<_> = ["node1", "node2", "node3"]
+///     .into_iter()
+///     .map(|node| {
+///         // all peers know node1 as seed
+///         let mut ring = HashRing::new(1, 10);
+///         ring.add("node1");
+///
+///         Gossip::new(node, network.connect(node), ring, GossipConfig::default())
+///     })
+///     .collect();
+///
+/// for _ in 0..20 {
+///     for peer in peers.iter_mut() {
+///         peer.tick();
+///     }
+/// }
+///
+/// for peer in peers.iter() {
+///     assert_eq!(3, peer.hashring().len());
+///     assert_eq!(peers[0].hashring().get(&"foo"), peer.hashring().get(&"foo"));
+/// }
+/// ```
+pub struct Gossip<T, Tr, S = DefaultHashBuilder, R = SimpleStrategy> {
+    me: T,
+    incarnation: u64,
+    transport: Tr,
+    config: GossipConfig,
+    members: Vec<Entry<T>>,
+    queue: Vec<(Member<T>, usize)>,
+    probe: Option<Probe<T>>,
+    order: Vec<T>,
+    relays: Vec<Relay<T>>,
+    now: u64,
+    seq: u64,
+    rng: u64,
+    membership: Membership<T, S, R>,
+}
+
+impl<T, Tr, S, R> Gossip<T, Tr, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    Tr: Transport<T>,
+    S: BuildHasher + Clone,
+    R: ReplicationStrategy<T> + Clone,
+{
+    /// Create a new `Gossip` peer
+    ///
+    /// # Arguments
+    ///
+    /// * `me` - this peer
+    /// * `transport` - implementation of Transport to send and receive messages
+    /// * `ring` - HashRing (replicas, vnodes, hasher and strategy) to maintain, its nodes are used as seeds
+    /// * `config` - timeouts and fan-out of the protocol
+    pub fn new(me: T, transport: Tr, ring: HashRing<T, S, R>, config: GossipConfig) -> Self {
+        let members = ring
+            .nodes()
+            .into_iter()
+            .filter(|node| *node != me)
+            .map(|node| Entry {
+                member: Member {
+                    node,
+                    state: MemberState::Alive,
+                    incarnation: 0,
+                },
+                since: 0,
+            })
+            .collect();
+
+        let rng = config.seed ^ DefaultHashBuilder.hash_one(&me);
+
+        let mut gossip = Gossip {
+            me,
+            incarnation: 0,
+            transport,
+            config,
+            members,
+            queue: vec![],
+            probe: None,
+            order: vec![],
+            relays: vec![],
+            now: 0,
+            seq: 0,
+            rng,
+            membership: Membership::new(ring),
+        };
+
+        gossip.enqueue(gossip.myself());
+        gossip.membership.update(gossip.view());
+
+        gossip
+    }
+
+    /// returns the HashRing of all members that are alive or suspected
+    pub fn hashring(&self) -> &HashRing<T, S, R> {
+        self.membership.hashring()
+    }
+
+    /// returns the current incarnation of this peer
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation
+    }
+
+    /// returns all known members including this peer and dead members
+    pub fn members(&self) -> Vec<Member<T>> {
+        std::iter::once(self.myself())
+            .chain(self.members.iter().map(|entry| entry.member.clone()))
+            .collect()
+    }
+
+    /// (re)join the cluster via `seed`, e.g. after this peer was declared dead
+    ///
+    /// Forgets all other members and announces this peer with a new incarnation.
+    pub fn join(&mut self, seed: T) -> Vec<MembershipEvent<T>> {
+        self.incarnation += 1;
+        self.members.clear();
+        self.queue.clear();
+        self.order.clear();
+        self.relays.clear();
+        self.probe = None;
+
+        if seed != self.me {
+            self.members.push(Entry {
+                member: Member {
+                    node: seed.clone(),
+                    state: MemberState::Alive,
+                    incarnation: 0,
+                },
+                since: self.now,
+            });
+        }
+
+        self.enqueue(self.myself());
+        self.start_probe(seed);
+
+        self.membership.update(self.view())
+    }
+
+    /// run one protocol period: handle all received messages, probe a member and update the HashRing
+    ///
+    /// returns the membership events (joined and left nodes, replication plans) caused by this tick
+    pub fn tick(&mut self) -> Vec<MembershipEvent<T>> {
+        self.now += 1;
+
+        for message in self.transport.receive() {
+            self.handle(message);
+        }
+
+        self.check_probe();
+        self.expire_suspects();
+
+        if self.probe.is_none()
+            && let Some(target) = self.next_target()
+        {
+            self.start_probe(target);
+        }
+
+        let now = self.now;
+        let timeout = self.config.probe_timeout;
+        self.relays.retain(|relay| now - relay.since < timeout);
+
+        self.membership.update(self.view())
+    }
+
+    fn handle(&mut self, message: Message<T>) {
+        match message {
+            Message::Ping { from, seq, updates } => {
+                let known = self.is_member(&from);
+                self.apply(updates);
+
+                let updates = match known {
+                    true => self.updates(),
+                    false => self.members(),
+                };
+                let ack = Message::Ack {
+                    from: self.me.clone(),
+                    seq,
+                    updates,
+                };
+                self.transport.send(&from, ack);
+            }
+            Message::PingReq {
+                from,
+                target,
+                seq,
+                updates,
+            } => {
+                self.apply(updates);
+
+                self.relays.push(Relay {
+                    target: target.clone(),
+                    seq,
+                    origin: from,
+                    since: self.now,
+                });
+                let ping = Message::Ping {
+                    from: self.me.clone(),
+                    seq,
+                    updates: self.updates(),
+                };
+                self.transport.send(&target, ping);
+            }
+            Message::Ack { from, seq, updates } => {
+                self.apply(updates);
+
+                if self
+                    .probe
+                    .as_ref()
+                    .is_some_and(|probe| probe.target == from && probe.seq == seq)
+                {
+                    self.probe = None;
+                }
+
+                let mut origins = vec![];
+                self.relays.retain(|relay| {
+                    let matches = relay.target == from && relay.seq == seq;
+                    if matches {
+                        origins.push(relay.origin.clone());
+                    }
+                    !matches
+                });
+
+                for origin in origins {
+                    let ack = Message::Ack {
+                        from: from.clone(),
+                        seq,
+                        updates: self.updates(),
+                    };
+                    self.transport.send(&origin, ack);
+                }
+            }
+        }
+    }
+
+    // merge membership changes of other peers into the local view
+    fn apply(&mut self, updates: Vec<Member<T>>) {
+        for update in updates {
+            if update.node == self.me {
+                if update.state != MemberState::Alive && update.incarnation >= self.incarnation {
+                    self.incarnation = update.incarnation + 1;
+                    self.enqueue(self.myself());
+                }
+                continue;
+            }
+
+            match self
+                .members
+                .iter_mut()
+                .find(|entry| entry.member.node == update.node)
+            {
+                Some(entry) if !overrides(&entry.member, &update) => continue,
+                Some(entry) => {
+                    entry.member = update.clone();
+                    entry.since = self.now;
+                }
+                None => self.members.push(Entry {
+                    member: update.clone(),
+                    since: self.now,
+                }),
+            }
+
+            self.enqueue(update);
+        }
+    }
+
+    fn check_probe(&mut self) {
+        let Some(probe) = self.probe.as_mut() else {
+            return;
+        };
+
+        let elapsed = self.now - probe.started;
+
+        if elapsed >= self.config.probe_timeout {
+            let target = probe.target.clone();
+            self.probe = None;
+            self.suspect(&target);
+        } else if elapsed >= self.config.ack_timeout && !probe.indirect {
+            probe.indirect = true;
+            let target = probe.target.clone();
+            let seq = probe.seq;
+
+            for helper in self.random_members(&target) {
+                let ping_req = Message::PingReq {
+                    from: self.me.clone(),
+                    target: target.clone(),
+                    seq,
+                    updates: self.updates(),
+                };
+                self.transport.send(&helper, ping_req);
+            }
+        }
+    }
+
+    fn suspect(&mut self, node: &T) {
+        let Some(entry) = self
+            .members
+            .iter_mut()
+            .find(|entry| entry.member.node == *node)
+        else {
+            return;
+        };
+
+        if entry.member.state == MemberState::Alive {
+            entry.member.state = MemberState::Suspect;
+            entry.since = self.now;
+
+            let member = entry.member.clone();
+            self.enqueue(member);
+        }
+    }
+
+    fn expire_suspects(&mut self) {
+        let mut dead = vec![];
+
+        for entry in self.members.iter_mut() {
+            if entry.member.state == MemberState::Suspect
+                && self.now - entry.since >= self.config.suspicion_timeout
+            {
+                entry.member.state = MemberState::Dead;
+                entry.since = self.now;
+                dead.push(entry.member.clone());
+            }
+        }
+
+        for member in dead {
+            self.enqueue(member);
+        }
+    }
+
+    fn start_probe(&mut self, target: T) {
+        self.seq += 1;
+
+        self.probe = Some(Probe {
+            target: target.clone(),
+            seq: self.seq,
+            started: self.now,
+            indirect: false,
+        });
+
+        let ping = Message::Ping {
+            from: self.me.clone(),
+            seq: self.seq,
+            updates: self.updates(),
+        };
+        self.transport.send(&target, ping);
+    }
+
+    // members are probed round-robin in random order, a new order is chosen after each round
+    fn next_target(&mut self) -> Option<T> {
+        while let Some(node) = self.order.pop() {
+            if self.is_member(&node) {
+                return Some(node);
+            }
+        }
+
+        self.order = self
+            .members
+            .iter()
+            .filter(|entry| entry.member.state != MemberState::Dead)
+            .map(|entry| entry.member.node.clone())
+            .collect();
+        shuffle(&mut self.order, &mut self.rng);
+
+        self.order.pop()
+    }
+
+    fn random_members(&mut self, exclude: &T) -> Vec<T> {
+        let mut candidates: Vec<T> = self
+            .members
+            .iter()
+            .filter(|entry| entry.member.state == MemberState::Alive)
+            .map(|entry| entry.member.node.clone())
+            .filter(|node| node != exclude)
+            .collect();
+
+        shuffle(&mut candidates, &mut self.rng);
+        candidates.truncate(self.config.indirect_probes);
+
+        candidates
+    }
+
+    // true if `node` is alive or suspected
+    fn is_member(&self, node: &T) -> bool {
+        self.members
+            .iter()
+            .any(|entry| entry.member.node == *node && entry.member.state != MemberState::Dead)
+    }
+
+    fn view(&self) -> Vec<T> {
+        std::iter::once(self.me.clone())
+            .chain(
+                self.members
+                    .iter()
+                    .filter(|entry| entry.member.state != MemberState::Dead)
+                    .map(|entry| entry.member.node.clone()),
+            )
+            .collect()
+    }
+
+    fn myself(&self) -> Member<T> {
+        Member {
+            node: self.me.clone(),
+            state: MemberState::Alive,
+            incarnation: self.incarnation,
+        }
+    }
+
+    fn enqueue(&mut self, member: Member<T>) {
+        self.queue.retain(|(queued, _)| queued.node != member.node);
+        self.queue.push((member, self.config.retransmits));
+    }
+
+    // membership changes to piggyback on the next message
+    fn updates(&mut self) -> Vec<Member<T>> {
+        let updates = self
+            .queue
+            .iter()
+            .map(|(member, _)| member.clone())
+            .collect();
+
+        for (_, remaining) in self.queue.iter_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        self.queue.retain(|(_, remaining)| *remaining > 0);
+
+        updates
+    }
+}
+
+// returns true if `update` is newer than `current` (SWIM precedence rules)
+fn overrides<T>(current: &Member<T>, update: &Member<T>) -> bool {
+    match update.state {
+        MemberState::Alive => update.incarnation > current.incarnation,
+        MemberState::Suspect => match current.state {
+            MemberState::Alive => update.incarnation >= current.incarnation,
+            MemberState::Suspect => update.incarnation > current.incarnation,
+            MemberState::Dead => false,
+        },
+        MemberState::Dead => {
+            current.state != MemberState::Dead && update.incarnation >= current.incarnation
+        }
+    }
+}
+
+fn shuffle<T>(items: &mut [T], rng: &mut u64) {
+    for i in (1..items.len()).rev() {
+        let j = (splitmix64(rng) % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const NODES: [&str; 5] = ["p0", "p1", "p2", "p3", "p4"];
+
+    type Peer = Gossip<&'static str, ChannelTransport<&'static str>>;
+
+    fn cluster() -> (ChannelNetwork<&'static str>, Vec<Peer>) {
+        let network = ChannelNetwork::new();
+
+        let peers = NODES
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let mut ring = HashRing::new(1, 10);
+                ring.add("p0");
+
+                let config = GossipConfig {
+                    seed: i as u64,
+                    ..GossipConfig::default()
+                };
+                Gossip::new(*node, network.connect(*node), ring, config)
+            })
+            .collect();
+
+        (network, peers)
+    }
+
+    fn run(peers: &mut [Peer], rounds: usize) -> Vec<MembershipEvent<&'static str>> {
+        let mut events = vec![];
+
+        for _ in 0..rounds {
+            for peer in peers.iter_mut() {
+                events.extend(peer.tick());
+            }
+        }
+
+        events
+    }
+
+    fn assert_same_ring(peers: &[Peer], expected: &[&str]) {
+        for peer in peers {
+            let mut nodes = peer.hashring().nodes();
+            nodes.sort();
+            assert_eq!(expected, nodes, "view of {}", peer.me);
+
+            assert_eq!(
+                peers[0].hashring().get_hash_ranges(),
+                peer.hashring().get_hash_ranges()
+            );
+        }
+    }
+
+    #[test]
+    fn peers_converge_to_the_same_ring() {
+        let (_, mut peers) = cluster();
+
+        let events = run(&mut peers, 30);
+
+        assert_same_ring(&peers, &NODES);
+        assert!(events.contains(&MembershipEvent::Joined("p4")));
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, MembershipEvent::Replicate { .. }))
+        );
+    }
+
+    #[test]
+    fn dead_peer_is_removed_and_can_rejoin() {
+        let (network, mut peers) = cluster();
+        run(&mut peers, 30);
+
+        network.set_down(&"p3", true);
+        let mut p3 = peers.remove(3);
+        let events = run(&mut peers, 60);
+
+        assert!(events.contains(&MembershipEvent::Left("p3")));
+        assert_same_ring(&peers, &["p0", "p1", "p2", "p4"]);
+
+        for peer in peers.iter() {
+            let member = peer.members().into_iter().find(|m| m.node == "p3").unwrap();
+            assert_eq!(MemberState::Dead, member.state);
+        }
+
+        network.set_down(&"p3", false);
+        p3.join("p0");
+        peers.push(p3);
+        run(&mut peers, 30);
+
+        assert_same_ring(&peers, &NODES);
+    }
+
+    #[test]
+    fn suspected_peer_refutes_with_higher_incarnation() {
+        let (_, mut peers) = cluster();
+        run(&mut peers, 30);
+
+        peers[0].apply(vec![Member {
+            node: "p2",
+            state: MemberState::Suspect,
+            incarnation: 0,
+        }]);
+        run(&mut peers, 20);
+
+        assert_eq!(1, peers[2].incarnation());
+        for peer in peers.iter() {
+            for member in peer.members() {
+                assert_eq!(MemberState::Alive, member.state);
+            }
+        }
+        assert_same_ring(&peers, &NODES);
+    }
+
+    #[test]
+    fn precedence_of_updates() {
+        let member = |state, incarnation| Member {
+            node: "a",
+            state,
+            incarnation,
+        };
+        use MemberState::*;
+
+        assert!(overrides(&member(Alive, 1), &member(Alive, 2)));
+        assert!(!overrides(&member(Alive, 1), &member(Alive, 1)));
+        assert!(overrides(&member(Alive, 1), &member(Suspect, 1)));
+        assert!(!overrides(&member(Suspect, 1), &member(Suspect, 1)));
+        assert!(overrides(&member(Suspect, 1), &member(Alive, 2)));
+        assert!(overrides(&member(Suspect, 1), &member(Dead, 1)));
+        assert!(!overrides(&member(Alive, 2), &member(Dead, 1)));
+        assert!(overrides(&member(Dead, 1), &member(Alive, 2)));
+    }
+}
@@ -5,9 +5,30 @@ use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::hash::BuildHasher;
 
+pub mod analysis;
 pub mod coordinator;
 mod crud;
+mod datacenter;
+pub mod decommission;
+pub mod detector;
+#[cfg(test)]
+mod fixtures;
+pub mod format;
+pub mod handoff;
+pub mod hasher;
+pub mod impact;
 mod iterator;
+pub mod merkle;
+pub mod quorum;
+mod render;
+pub mod repair;
+mod selection;
+#[cfg(feature = "shared")]
+pub mod shared;
+pub mod strategy;
+pub mod token;
+
+use strategy::SimpleStrategy;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct DefaultHashBuilder;
@@ -65,11 +86,14 @@ impl<T> Ord for Node<T> {
 /// HashRing can calculate for each node which hashranges they are responsible for
 /// HashRing can calculate replication instructions if a cluster changes or if the cluster if replaced completely to find target nodes and source nodes with affected hashranges
 #[derive(Clone, PartialEq, Debug)]
-pub struct HashRing<T, S = DefaultHashBuilder> {
+pub struct HashRing<T, S = DefaultHashBuilder, R = SimpleStrategy> {
     hash_builder: S,
     ring: Vec<Node<T>>,
+    // number of real nodes, i.e. virtual nodes with virtual_id 0
+    len: usize,
     replicas: usize,
     vnodes: usize,
+    strategy: R,
 }
 
 impl<T> Default for HashRing<T> {
@@ -77,8 +101,10 @@ impl<T> Default for HashRing<T> {
         HashRing {
             hash_builder: DefaultHashBuilder,
             ring: Vec::new(),
+            len: 0,
             replicas: 2,
             vnodes: 200,
+            strategy: SimpleStrategy,
         }
     }
 }
@@ -97,16 +123,53 @@ impl<T> HashRing<T> {
         HashRing {
             hash_builder: DefaultHashBuilder,
             ring: Vec::new(),
+            len: 0,
             replicas,
             vnodes: vnodes.max(1),
+            strategy: SimpleStrategy,
         }
     }
 }
 
-impl<T, S> HashRing<T, S> {
+impl<T, R> HashRing<T, DefaultHashBuilder, R> {
+    /// Create a new `HashRing` which will use the given replication strategy to select the nodes for each key.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key, strategies may interpret or ignore this value
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    /// * `strategy` - implementation of ReplicationStrategy to select primary and replica nodes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, RandomStrategy};
+    ///
+    /// let mut ring = HashRing::with_strategy(1, 10, RandomStrategy::new(42));
+    /// ring.batch_add(vec!["node1", "node2", "node3"]);
+    ///
+    /// assert_eq!(2, ring.get(&"foo").len());
+    /// ```
+    pub fn with_strategy(
+        replicas: usize,
+        vnodes: usize,
+        strategy: R,
+    ) -> HashRing<T, DefaultHashBuilder, R> {
+        HashRing {
+            hash_builder: DefaultHashBuilder,
+            ring: Vec::new(),
+            len: 0,
+            replicas,
+            vnodes: vnodes.max(1),
+            strategy,
+        }
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R> {
     /// Get the number of real nodes in the hash ring.
     pub fn len(&self) -> usize {
-        self.ring.len() / self.vnodes
+        self.len
     }
 
     /// Get the number of virtual nodes in the hash ring.
@@ -118,6 +181,43 @@ impl<T, S> HashRing<T, S> {
     pub fn is_empty(&self) -> bool {
         self.ring.len() == 0
     }
+
+    /// Returns the number of nodes storing copies of each key in addition to the primary node.
+    pub fn replicas(&self) -> usize {
+        self.replicas
+    }
+
+    /// Returns the replication strategy used to select primary and replica nodes.
+    pub fn strategy(&self) -> &R {
+        &self.strategy
+    }
+
+    /// Creates an empty `HashRing` which will use the given hash builder and replication strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key, strategies may interpret or ignore this value
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    /// * `hash_builder` - implementation of BuildHasher to provider a Hasher for the HashRing
+    /// * `strategy` - implementation of ReplicationStrategy to select primary and replica nodes
+    pub fn with_hasher_and_strategy(
+        replicas: usize,
+        vnodes: usize,
+        hash_builder: S,
+        strategy: R,
+    ) -> HashRing<T, S, R> {
+        HashRing {
+            hash_builder,
+            ring: Vec::new(),
+            len: 0,
+            replicas,
+            vnodes: vnodes.max(1),
+            strategy,
+        }
+    }
+}
+
+impl<T, S> HashRing<T, S> {
     /// Creates an empty `HashRing` which will use the given hash builder.
     ///
     /// # Arguments
@@ -158,8 +258,10 @@ impl<T, S> HashRing<T, S> {
         HashRing {
             hash_builder,
             ring: Vec::new(),
+            len: 0,
             replicas,
-            vnodes,
+            vnodes: vnodes.max(1),
+            strategy: SimpleStrategy,
         }
     }
 }
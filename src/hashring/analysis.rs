@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::HashRing;
+use super::coordinator::{Replicas, share};
+use super::strategy::ReplicationStrategy;
+
+/// hash ranges that lost copies, as returned by `HashRing::analyze_failures`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct UnderReplication<T> {
+    /// hash ranges grouped by the number of surviving copies, the nodes of each range are the surviving nodes
+    pub ranges: BTreeMap<usize, Vec<Replicas<T>>>,
+}
+
+impl<T> UnderReplication<T> {
+    /// returns true if no hash range lost a copy
+    pub fn is_healthy(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// returns all hash ranges that lost all copies
+    pub fn lost(&self) -> &[Replicas<T>] {
+        self.ranges.get(&0).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// returns the share of the hash space with the given number of surviving copies, between 0.0 and 1.0
+    pub fn share(&self, copies: usize) -> f64 {
+        let width: u128 = self
+            .ranges
+            .get(&copies)
+            .map(|ranges| ranges.iter().map(Replicas::width).sum())
+            .unwrap_or(0);
+
+        share(width)
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// returns all hash ranges that lose copies, if the given nodes failed at the same time
+    ///
+    /// The baseline of each hash range are the nodes the replication strategy assigns to it, which may be fewer than
+    /// `replicas + 1` (e.g. in small rings).
+    ///
+    /// # Arguments
+    ///
+    /// * `failed` - nodes that failed, they are still part of the ring (not replaced yet)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+    ///
+    /// let analysis = ring.analyze_failures(&["node1", "node2"]);
+    ///
+    /// for replicas in analysis.lost() {
+    ///     // all keys within replicas.hash_range are lost
+    ///     assert!(replicas.nodes.is_empty());
+    /// }
+    /// assert!(!analysis.ranges[&1].is_empty());
+    /// ```
+    pub fn analyze_failures(&self, failed: &[T]) -> UnderReplication<T> {
+        let mut ranges: BTreeMap<usize, Vec<Replicas<T>>> = BTreeMap::new();
+
+        for mut replicas in self.get_hash_ranges() {
+            let owners = replicas.nodes.len();
+            replicas.nodes.retain(|node| !failed.contains(node));
+
+            let copies = replicas.nodes.len();
+            if copies < owners {
+                ranges.entry(copies).or_default().push(replicas);
+            }
+        }
+
+        for ranges in ranges.values_mut() {
+            ranges.sort_by_key(|replicas| *replicas.hash_range.start());
+        }
+
+        UnderReplication { ranges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::fixtures::ring;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn no_failures_means_healthy() {
+        let analysis = ring(2, &["a", "b", "c", "d", "e"]).analyze_failures(&[]);
+
+        assert!(analysis.is_healthy());
+        assert!(analysis.lost().is_empty());
+    }
+
+    #[test]
+    fn small_ring_is_healthy_without_failures() {
+        let ring = ring(2, &["a", "b"]);
+
+        assert!(ring.analyze_failures(&[]).is_healthy());
+
+        let analysis = ring.analyze_failures(&["a"]);
+        assert_eq!(vec![&1], analysis.ranges.keys().collect::<Vec<_>>());
+        assert!(analysis.ranges[&1].iter().all(|r| r.nodes == vec!["b"]));
+        assert!((analysis.share(1) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranges_are_grouped_by_surviving_copies() {
+        let ring = ring(2, &["a", "b", "c", "d", "e"]);
+        let failed = ["a", "b", "c"];
+
+        let analysis = ring.analyze_failures(&failed);
+
+        assert_eq!(vec![&0, &1, &2], analysis.ranges.keys().collect::<Vec<_>>());
+
+        for (copies, ranges) in analysis.ranges.iter() {
+            for replicas in ranges {
+                assert_eq!(*copies, replicas.nodes.len());
+
+                let hash = *replicas.hash_range.end();
+                let owners = ring.get_by_hash(hash);
+                let surviving: Vec<_> =
+                    owners.into_iter().filter(|n| !failed.contains(n)).collect();
+                assert_eq!(surviving, replicas.nodes);
+            }
+        }
+
+        let total: f64 = (0..3).map(|copies| analysis.share(copies)).sum();
+        assert!(
+            (total - 1.0).abs() < 1e-9,
+            "every range lost at least one copy"
+        );
+        assert!(analysis.share(0) > 0.0);
+    }
+}
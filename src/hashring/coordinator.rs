@@ -7,6 +7,7 @@ use std::ops::RangeInclusive;
 use serde::{Deserialize, Serialize};
 
 use super::HashRing;
+use super::strategy::ReplicationStrategy;
 
 /// Replicas contains a hashrange and all nodes that store keys within the given range
 /// The first node in `nodes` is the primary node, the following nodes are replication nodes
@@ -20,15 +21,92 @@ pub struct Replicas<T> {
     pub nodes: Vec<T>,
 }
 
-impl<T> HashRing<T>
+impl<T> Replicas<T> {
+    /// returns the number of hashes within `hash_range`
+    pub fn width(&self) -> u128 {
+        width(&self.hash_range)
+    }
+}
+
+impl<T> Replicas<T>
+where
+    T: Clone,
+{
+    /// split the hash range into `n` chunks of (almost) equal width, all chunks keep the same nodes
+    ///
+    /// Returns fewer chunks if the hash range contains less than `n` hashes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::Replicas;
+    ///
+    /// let replicas = Replicas {
+    ///     hash_range: 0..=9,
+    ///     nodes: vec!["node1", "node2"],
+    /// };
+    ///
+    /// let chunks = replicas.split(3);
+    ///
+    /// assert_eq!(0..=3, chunks[0].hash_range);
+    /// assert_eq!(4..=6, chunks[1].hash_range);
+    /// assert_eq!(7..=9, chunks[2].hash_range);
+    /// assert_eq!(vec!["node1", "node2"], chunks[2].nodes);
+    /// ```
+    pub fn split(&self, n: usize) -> Vec<Replicas<T>> {
+        let width = self.width();
+        let n = (n.max(1) as u128).min(width);
+
+        let mut chunks = vec![];
+        let mut start = *self.hash_range.start() as u128;
+
+        for i in 0..n {
+            // the first (width % n) chunks are one hash wider
+            let chunk = width / n + u128::from(i < width % n);
+            chunks.push(self.chunk(start, start + chunk - 1));
+            start += chunk;
+        }
+
+        chunks
+    }
+
+    /// split the hash range into chunks containing at most `width` hashes, all chunks keep the same nodes
+    pub fn split_by_width(&self, width: u64) -> Vec<Replicas<T>> {
+        let width = width.max(1) as u128;
+
+        let mut chunks = vec![];
+        let mut start = *self.hash_range.start() as u128;
+        let end = *self.hash_range.end() as u128;
+
+        while start <= end {
+            let chunk_end = (start + width - 1).min(end);
+            chunks.push(self.chunk(start, chunk_end));
+            start = chunk_end + 1;
+        }
+
+        chunks
+    }
+
+    fn chunk(&self, start: u128, end: u128) -> Replicas<T> {
+        Replicas {
+            hash_range: start as u64..=end as u64,
+            nodes: self.nodes.clone(),
+        }
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
 where
     T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
 {
     pub fn get_hash_ranges(&self) -> Vec<Replicas<T>> {
+        // a single node covers the whole ring, the strategy still decides whether it stores any keys
         if self.len() == 1 {
             return vec![Replicas {
                 hash_range: 0..=u64::MAX,
-                nodes: vec![self.ring.first().unwrap().node.clone()],
+                nodes: self.get_by_hash(0),
             }];
         }
 
@@ -45,16 +123,16 @@ where
             if left.key > right.key {
                 replication_setup.push(Replicas {
                     hash_range: left.key + 1..=u64::MAX,
-                    nodes: self.get(&(right.node.clone(), right.virtual_id)),
+                    nodes: self.get_by_hash(right.key),
                 });
                 replication_setup.push(Replicas {
                     hash_range: 0..=right.key,
-                    nodes: self.get(&(right.node.clone(), right.virtual_id)),
+                    nodes: self.get_by_hash(right.key),
                 });
             } else {
                 replication_setup.push(Replicas {
                     hash_range: left.key + 1..=right.key,
-                    nodes: self.get(&(right.node.clone(), right.virtual_id)),
+                    nodes: self.get_by_hash(right.key),
                 });
             }
 
@@ -127,12 +205,16 @@ where
     ///
     ///
     /// ```
-    pub fn find_sources(
+    pub fn find_sources<S2, R2>(
         &self,
         target: &T,
-        source: &HashRing<T>,
+        source: &HashRing<T, S2, R2>,
         available_nodes: &[T],
-    ) -> Vec<Replicas<T>> {
+    ) -> Vec<Replicas<T>>
+    where
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+    {
         let mut sources = vec![];
 
         let from = source.get_hash_ranges();
@@ -152,6 +234,8 @@ where
                         continue;
                     }
 
+                    self.strategy.rank_sources(target, &mut nodes);
+
                     sources.push(Replicas {
                         hash_range: range,
                         nodes,
@@ -163,6 +247,27 @@ where
         self.merge_replicas(sources)
     }
 
+    /// same as `find_sources`, but hash ranges are split into chunks containing at most `max_width` hashes
+    ///
+    /// Each chunk keeps the source nodes of its hash range. Smaller chunks can be transferred in parallel
+    /// and allow to track (and resume) the progress of a long running replication.
+    pub fn find_sources_chunked<S2, R2>(
+        &self,
+        target: &T,
+        source: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+        max_width: u64,
+    ) -> Vec<Replicas<T>>
+    where
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+    {
+        self.find_sources(target, source, available_nodes)
+            .into_iter()
+            .flat_map(|replicas| replicas.split_by_width(max_width))
+            .collect()
+    }
+
     /// merge hashranges together, if the hashrange touch and all affected nodes are identical
     pub fn merge_replicas(&self, mut replicas: Vec<Replicas<T>>) -> Vec<Replicas<T>> {
         replicas.sort_by(|a, b| a.hash_range.start().cmp(b.hash_range.start()));
@@ -209,7 +314,7 @@ where
     }
 }
 
-fn intersect<T: Ord + Copy>(
+pub(crate) fn intersect<T: Ord + Copy>(
     a: &RangeInclusive<T>,
     b: &RangeInclusive<T>,
 ) -> Option<RangeInclusive<T>> {
@@ -223,6 +328,16 @@ fn intersect<T: Ord + Copy>(
     }
 }
 
+// returns the number of hashes within `range`
+pub(crate) fn width(range: &RangeInclusive<u64>) -> u128 {
+    *range.end() as u128 - *range.start() as u128 + 1
+}
+
+// returns the share of the ring covered by the given number of hashes, between 0.0 and 1.0
+pub(crate) fn share(hashes: u128) -> f64 {
+    hashes as f64 / (u64::MAX as f64 + 1.0)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::hashring::HashRing;
@@ -250,6 +365,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn split_replicas_into_chunks() {
+        let replicas = Replicas {
+            hash_range: 0..=u64::MAX,
+            nodes: vec!["node1"],
+        };
+
+        let chunks = replicas.split(4);
+        assert_eq!(4, chunks.len());
+        assert_eq!(0..=(1 << 62) - 1, chunks[0].hash_range);
+        assert_eq!((3 << 62)..=u64::MAX, chunks[3].hash_range);
+        assert_eq!(replicas.width(), chunks.iter().map(|c| c.width()).sum());
+
+        let small = Replicas {
+            hash_range: 10..=11,
+            nodes: vec!["node1"],
+        };
+        assert_eq!(2, small.split(5).len());
+        assert_eq!(vec![small.clone()], small.split(0));
+    }
+
+    #[test]
+    fn split_replicas_by_width() {
+        let replicas = Replicas {
+            hash_range: 10..=34,
+            nodes: vec!["node1", "node2"],
+        };
+
+        let expected = vec![
+            Replicas {
+                hash_range: 10..=19,
+                nodes: vec!["node1", "node2"],
+            },
+            Replicas {
+                hash_range: 20..=29,
+                nodes: vec!["node1", "node2"],
+            },
+            Replicas {
+                hash_range: 30..=34,
+                nodes: vec!["node1", "node2"],
+            },
+        ];
+        assert_eq!(expected, replicas.split_by_width(10));
+
+        let full = Replicas {
+            hash_range: 0..=u64::MAX,
+            nodes: vec!["node1"],
+        };
+        assert_eq!(2, full.split_by_width(u64::MAX).len());
+    }
+
+    #[test]
+    fn find_sources_chunked_keeps_nodes() {
+        let node1 = Node::new("127.0.0.1");
+        let node2 = Node::new("127.0.0.2");
+        let node3 = Node::new("127.0.0.3");
+
+        let mut ring_original = HashRing::new(1, 5);
+        ring_original.batch_add(vec![node1, node2]);
+
+        let mut ring_new = ring_original.clone();
+        ring_new.add(node3);
+
+        let sources = ring_new.find_sources(&node3, &ring_original, &[node1, node2]);
+        let chunks =
+            ring_new.find_sources_chunked(&node3, &ring_original, &[node1, node2], 1 << 60);
+
+        assert!(chunks.len() > sources.len());
+        assert!(chunks.iter().all(|c| c.width() <= 1 << 60));
+
+        let total: u128 = sources.iter().map(|s| s.width()).sum();
+        assert_eq!(total, chunks.iter().map(|c| c.width()).sum());
+    }
+
     #[test]
     fn hash_ranges_find_sources_minimal() {
         let node1 = Node::new("127.0.0.1"); // @1093046220658055553
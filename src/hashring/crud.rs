@@ -3,12 +3,14 @@ use std::{
     hash::{BuildHasher, Hash},
 };
 
+use super::strategy::ReplicationStrategy;
 use super::{HashRing, Node};
 
-impl<T, S> HashRing<T, S>
+impl<T, S, R> HashRing<T, S, R>
 where
     T: Hash + Clone + Debug + PartialEq,
     S: BuildHasher,
+    R: ReplicationStrategy<T>,
 {
     /// Add `node` to the hash ring.
     pub fn add(&mut self, node: T) {
@@ -22,6 +24,7 @@ where
             let key = self.get_hash(&(&node, id));
             self.ring.push(Node::new(key, node.clone(), id)); // TODO: avoid duplicates
         }
+        self.len += 1;
     }
 
     pub fn batch_add(&mut self, nodes: Vec<T>) {
@@ -36,42 +39,48 @@ where
     where
         T: PartialEq,
     {
+        let removed = self
+            .ring
+            .iter()
+            .filter(|n| n.node == *node && n.virtual_id == 0)
+            .count();
+
         self.ring.retain(|n| n.node != *node);
+        self.len -= removed;
     }
 
     /// returns all real nodes responsible for `key`
     ///
     /// Returns an empty array if the ring is empty
     pub fn get<U: Hash>(&self, key: &U) -> Vec<T> {
+        self.get_by_hash(self.get_hash(key))
+    }
+
+    /// returns all real nodes responsible for the given position (hash) on the ring
+    ///
+    /// Returns an empty array if the ring is empty
+    pub fn get_by_hash(&self, hash: u64) -> Vec<T> {
         if self.ring.is_empty() {
             return vec![];
         }
 
-        let limit = (self.replicas + 1).min(self.len());
-
-        let hash = self.get_hash(key);
-
-        let n = match self.ring.binary_search_by(|node| node.key.cmp(&hash)) {
-            Err(n) => n,
-            Ok(n) => n,
-        };
-
-        let mut nodes = self.ring.clone();
-        nodes.rotate_left(n);
+        let n = self.position(hash);
 
-        let mut replica_nodes = vec![];
+        let clockwise = self.ring[n..]
+            .iter()
+            .chain(self.ring[..n].iter())
+            .map(|vnode| &vnode.node);
 
-        for node in nodes {
-            if !replica_nodes.contains(&node.node) {
-                replica_nodes.push(node.node.clone());
+        self.strategy
+            .select(self.ring[n].key, clockwise, self.replicas)
+    }
 
-                if replica_nodes.len() == limit {
-                    break;
-                }
-            }
+    // returns the index of the first virtual node at or after `hash` (clockwise), the ring must not be empty
+    pub(super) fn position(&self, hash: u64) -> usize {
+        match self.ring.binary_search_by(|node| node.key.cmp(&hash)) {
+            Err(n) => n % self.ring.len(),
+            Ok(n) => n,
         }
-
-        replica_nodes
     }
 
     /// returns the hash for a given key or node (as used in this HashRing)
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use super::coordinator::Replicas;
+use super::strategy::{Datacenter, NetworkTopologyStrategy, ReplicationStrategy};
+use super::{DefaultHashBuilder, HashRing};
+
+impl<T> HashRing<T, DefaultHashBuilder, NetworkTopologyStrategy> {
+    /// Create a new `HashRing` spanning multiple datacenters, storing a fixed number of copies per datacenter.
+    /// Nodes declare their datacenter by implementing `Datacenter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    /// * `copies` - number of copies (primary + replicas) per datacenter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{Datacenter, HashRing};
+    ///
+    /// #[derive(Debug, Clone, Hash, PartialEq)]
+    /// struct Node {
+    ///     name: &'static str,
+    ///     dc: &'static str,
+    /// }
+    ///
+    /// impl Datacenter for Node {
+    ///     fn datacenter(&self) -> &str {
+    ///         self.dc
+    ///     }
+    /// }
+    ///
+    /// let mut ring = HashRing::with_datacenters(10, [("dc1", 2), ("dc2", 1)]);
+    /// ring.batch_add(vec![
+    ///     Node { name: "a1", dc: "dc1" },
+    ///     Node { name: "a2", dc: "dc1" },
+    ///     Node { name: "b1", dc: "dc2" },
+    ///     Node { name: "b2", dc: "dc2" },
+    /// ]);
+    ///
+    /// let nodes = ring.get_per_datacenter(&"foo");
+    ///
+    /// assert_eq!(2, nodes["dc1"].len());
+    /// assert_eq!(1, nodes["dc2"].len());
+    /// ```
+    pub fn with_datacenters<D: Into<String>>(
+        vnodes: usize,
+        copies: impl IntoIterator<Item = (D, usize)>,
+    ) -> Self {
+        let strategy = NetworkTopologyStrategy::new(copies);
+        let total: usize = strategy
+            .datacenters()
+            .filter_map(|dc| strategy.copies(dc))
+            .sum();
+
+        HashRing::with_strategy(total.saturating_sub(1), vnodes, strategy)
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq + Datacenter,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// returns all real nodes responsible for `key` grouped by their datacenter
+    ///
+    /// Within each datacenter the nodes keep the order returned by `get`.
+    pub fn get_per_datacenter<U: Hash>(&self, key: &U) -> BTreeMap<String, Vec<T>> {
+        let mut datacenters: BTreeMap<String, Vec<T>> = BTreeMap::new();
+
+        for node in self.get(key) {
+            datacenters
+                .entry(node.datacenter().to_string())
+                .or_default()
+                .push(node);
+        }
+
+        datacenters
+    }
+}
+
+impl<T> Replicas<T>
+where
+    T: Datacenter,
+{
+    /// returns the nodes of this hash range grouped by their datacenter
+    pub fn datacenters(&self) -> BTreeMap<&str, Vec<&T>> {
+        let mut datacenters: BTreeMap<&str, Vec<&T>> = BTreeMap::new();
+
+        for node in self.nodes.iter() {
+            datacenters.entry(node.datacenter()).or_default().push(node);
+        }
+
+        datacenters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::HashRing;
+    use crate::hashring::fixtures::DcNode as Node;
+    use pretty_assertions::assert_eq;
+
+    fn nodes() -> Vec<Node> {
+        vec![
+            Node::new("a1", "dc1"),
+            Node::new("a2", "dc1"),
+            Node::new("a3", "dc1"),
+            Node::new("a4", "dc1"),
+            Node::new("b1", "dc2"),
+            Node::new("b2", "dc2"),
+            Node::new("b3", "dc2"),
+            Node::new("c1", "dc3"),
+        ]
+    }
+
+    #[test]
+    fn multi_dc_ring_stores_copies_per_datacenter() {
+        let mut ring = HashRing::with_datacenters(10, [("dc1", 3), ("dc2", 2)]);
+        ring.batch_add(nodes());
+
+        for key in 0..100 {
+            let datacenters = ring.get_per_datacenter(&key);
+
+            assert_eq!(vec!["dc1", "dc2"], datacenters.keys().collect::<Vec<_>>());
+            assert_eq!(3, datacenters["dc1"].len());
+            assert_eq!(2, datacenters["dc2"].len());
+        }
+
+        for replicas in ring.get_hash_ranges() {
+            let datacenters = replicas.datacenters();
+
+            assert_eq!(3, datacenters["dc1"].len());
+            assert_eq!(2, datacenters["dc2"].len());
+            assert!(!datacenters.contains_key("dc3"));
+        }
+    }
+
+    #[test]
+    fn find_sources_prefers_same_datacenter() {
+        let mut ring = HashRing::with_datacenters(10, [("dc1", 2), ("dc2", 2)]);
+        ring.batch_add(nodes());
+
+        let joining = Node::new("b4", "dc2");
+        let mut ring_new = ring.clone();
+        ring_new.add(joining.clone());
+
+        let sources = ring_new.find_sources(&joining, &ring, &nodes());
+        assert!(!sources.is_empty());
+
+        for replicas in sources {
+            assert_eq!("dc2", replicas.nodes[0].dc);
+            assert_eq!(4, replicas.nodes.len());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::HashRing;
+use super::coordinator::Replicas;
+use super::strategy::ReplicationStrategy;
+
+/// hash range that needs to be copied to `target` before a node leaves the ring
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct DecommissionTransfer<T> {
+    pub target: T,
+    /// hash range and source nodes, the leaving node comes first (it is still live and can push the range)
+    pub replicas: Replicas<T>,
+    /// number of copies of this hash range that are left once the leaving node is gone
+    pub remaining_copies: usize,
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher + Clone,
+    R: ReplicationStrategy<T> + Clone,
+{
+    /// returns all hash ranges that need to be copied while `node` is still part of the ring, so that no hash range
+    /// has fewer than `replicas + 1` copies after `node` was removed
+    ///
+    /// Transfers are ordered by the number of remaining copies (fewest first), thus the hash ranges at risk are
+    /// copied first. Returns an empty list if `node` is not part of the ring. All nodes are treated as available,
+    /// use `plan_decommission_with` if some of them are down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+    ///
+    /// for transfer in ring.plan_decommission(&"node2") {
+    ///     assert_eq!("node2", transfer.replicas.nodes[0]);
+    ///     assert_eq!(1, transfer.remaining_copies);
+    ///     // push transfer.replicas.hash_range from node2 to transfer.target
+    /// }
+    ///
+    /// ring.remove(&"node2");
+    /// ```
+    pub fn plan_decommission(&self, node: &T) -> Vec<DecommissionTransfer<T>> {
+        self.plan_decommission_with(node, &self.nodes())
+    }
+
+    /// same as `plan_decommission`, but only the available nodes count as remaining copies, sources and targets
+    ///
+    /// Transfers are ordered by the number of remaining copies on available nodes (fewest first).
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - node to decommission, it is treated as available (it pushes its hash ranges before it leaves)
+    /// * `available_nodes` - nodes that are currently up
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+    ///
+    /// // node4 is down
+    /// for transfer in ring.plan_decommission_with(&"node2", &["node1", "node3"]) {
+    ///     assert_ne!("node4", transfer.target);
+    ///     assert!(!transfer.replicas.nodes.contains(&"node4"));
+    /// }
+    /// ```
+    pub fn plan_decommission_with(
+        &self,
+        node: &T,
+        available_nodes: &[T],
+    ) -> Vec<DecommissionTransfer<T>> {
+        if !self.nodes().contains(node) {
+            return vec![];
+        }
+
+        let mut available = available_nodes.to_vec();
+        if !available.contains(node) {
+            available.push(node.clone());
+        }
+
+        let mut without = self.clone();
+        without.remove(node);
+
+        let mut transfers = vec![];
+
+        // nodes that are down can neither receive nor provide hash ranges
+        for target in without
+            .nodes()
+            .into_iter()
+            .filter(|n| available.contains(n))
+        {
+            for mut replicas in without.find_sources(&target, self, &available) {
+                let remaining_copies = replicas.nodes.iter().filter(|n| *n != node).count();
+
+                if let Some(i) = replicas.nodes.iter().position(|n| n == node) {
+                    let leaving = replicas.nodes.remove(i);
+                    replicas.nodes.insert(0, leaving);
+                }
+
+                transfers.push(DecommissionTransfer {
+                    target: target.clone(),
+                    replicas,
+                    remaining_copies,
+                });
+            }
+        }
+
+        transfers.sort_by_key(|t| (t.remaining_copies, *t.replicas.hash_range.start()));
+
+        transfers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::fixtures::ring;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn decommission_covers_all_ranges_of_the_leaving_node() {
+        let ring = ring(2, &["a", "b", "c", "d", "e"]);
+
+        let transfers = ring.plan_decommission(&"c");
+        assert!(!transfers.is_empty());
+
+        let mut without = ring.clone();
+        without.remove(&"c");
+
+        for transfer in transfers.iter() {
+            assert_eq!("c", transfer.replicas.nodes[0]);
+            assert_eq!(2, transfer.remaining_copies);
+            assert!(
+                !transfer.replicas.nodes[1..].contains(&transfer.target),
+                "target does not store the range yet"
+            );
+
+            let hash = *transfer.replicas.hash_range.end();
+            assert!(ring.get_by_hash(hash).contains(&"c"));
+            assert!(without.get_by_hash(hash).contains(&transfer.target));
+        }
+
+        // after all transfers, every hash range has replicas + 1 copies again
+        for replicas in without.get_hash_ranges() {
+            let hash = *replicas.hash_range.end();
+            for owner in replicas.nodes {
+                let copied = transfers
+                    .iter()
+                    .any(|t| t.target == owner && t.replicas.hash_range.contains(&hash));
+                assert!(ring.get_by_hash(hash).contains(&owner) || copied);
+            }
+        }
+    }
+
+    #[test]
+    fn ranges_with_fewest_remaining_copies_go_first() {
+        let ring = ring(2, &["a", "b", "c", "d", "e", "f"]);
+
+        let available = ["a", "b", "c", "e", "f"];
+        let transfers = ring.plan_decommission_with(&"c", &available);
+
+        let copies: Vec<usize> = transfers.iter().map(|t| t.remaining_copies).collect();
+        assert!(copies.contains(&1), "ranges also stored on d lost a copy");
+        assert!(copies.contains(&2));
+        assert!(copies.windows(2).all(|w| w[0] <= w[1]));
+
+        for transfer in transfers.iter() {
+            assert_eq!("c", transfer.replicas.nodes[0]);
+            assert_ne!("d", transfer.target);
+            assert!(!transfer.replicas.nodes.contains(&"d"));
+            assert_eq!(transfer.replicas.nodes.len() - 1, transfer.remaining_copies);
+
+            let hash = *transfer.replicas.hash_range.end();
+            let owners = ring.get_by_hash(hash);
+            let surviving = owners.iter().filter(|n| **n != "c" && **n != "d").count();
+            assert_eq!(surviving, transfer.remaining_copies);
+        }
+
+        assert!(ring.plan_decommission_with(&"x", &available).is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::HashRing;
+use super::strategy::ReplicationStrategy;
+
+/// Clock returns the time elapsed since an arbitrary, fixed point in time
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+/// Clock based on `Instant`
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock that only moves if advanced manually, e.g. for deterministic tests
+///
+/// All clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a new `ManualClock` starting at 0
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+/// FailureDetector returns how likely a node failed, used by `get_healthy` and `get_by_suspicion`
+pub trait FailureDetector<T> {
+    /// suspicion level of `node`, higher values mean the node more likely failed
+    fn phi(&self, node: &T) -> f64;
+
+    /// returns true if `node` is considered available
+    fn is_available(&self, node: &T) -> bool;
+}
+
+/// thresholds of `PhiAccrualDetector`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhiAccrualConfig {
+    /// nodes with a phi greater or equal than this value are considered failed (8.0 ~ 1 false positive in 10^8)
+    pub threshold: f64,
+    /// number of heartbeat intervals used to estimate the distribution
+    pub max_samples: usize,
+    /// lower bound of the standard deviation, to avoid false positives for very regular heartbeats
+    pub min_std_deviation: Duration,
+    /// additional pause that is tolerated before a node is suspected, e.g. for garbage collection
+    pub acceptable_heartbeat_pause: Duration,
+    /// expected heartbeat interval until the first intervals were measured
+    pub first_heartbeat_estimate: Duration,
+}
+
+impl Default for PhiAccrualConfig {
+    fn default() -> Self {
+        PhiAccrualConfig {
+            threshold: 8.0,
+            max_samples: 100,
+            min_std_deviation: Duration::from_millis(100),
+            acceptable_heartbeat_pause: Duration::ZERO,
+            first_heartbeat_estimate: Duration::from_secs(1),
+        }
+    }
+}
+
+// heartbeat intervals of one node in milliseconds
+struct History<T> {
+    node: T,
+    last: Duration,
+    intervals: VecDeque<f64>,
+}
+
+/// phi accrual failure detector (Hayashibara et al.), as used by Cassandra and Akka
+///
+/// Records the heartbeats of each node and estimates the distribution of their intervals. The suspicion level phi
+/// grows with the time since the last heartbeat, relative to the usual interval: phi = 1 means a 10% chance that
+/// the node is still alive (and its heartbeat just late), phi = 2 means 1%, phi = 3 means 0.1% and so on.
+/// Nodes without any heartbeat are considered available.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use hashring_coordinator::{HashRing, ManualClock, PhiAccrualConfig, PhiAccrualDetector};
+///
+/// let mut ring = HashRing::new(2, 10);
+/// ring.batch_add(vec!["node1", "node2", "node3"]);
+///
+/// let clock = ManualClock::new();
+/// let mut detector = PhiAccrualDetector::new(clock.clone(), PhiAccrualConfig::default());
+///
+/// for _ in 0..10 {
+///     clock.advance(Duration::from_secs(1));
+///     for node in ["node1", "node2", "node3"] {
+///         detector.heartbeat(&node);
+///     }
+/// }
+///
+/// // node2 stops sending heartbeats
+/// for _ in 0..5 {
+///     clock.advance(Duration::from_secs(1));
+///     detector.heartbeat(&"node1");
+///     detector.heartbeat(&"node3");
+/// }
+///
+/// let nodes = ring.get_healthy(&"foo", &detector);
+///
+/// assert_eq!(2, nodes.len());
+/// assert!(!nodes.contains(&"node2"));
+/// assert_eq!(Some(&"node2"), ring.get_by_suspicion(&"foo", &detector).last());
+/// ```
+pub struct PhiAccrualDetector<T, C = SystemClock> {
+    clock: C,
+    config: PhiAccrualConfig,
+    histories: Vec<History<T>>,
+}
+
+impl<T, C> PhiAccrualDetector<T, C>
+where
+    T: Clone + PartialEq,
+    C: Clock,
+{
+    /// Create a new `PhiAccrualDetector`
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - implementation of Clock, use `SystemClock::default()` outside of tests
+    /// * `config` - thresholds of the detector
+    pub fn new(clock: C, config: PhiAccrualConfig) -> Self {
+        PhiAccrualDetector {
+            clock,
+            config,
+            histories: vec![],
+        }
+    }
+
+    /// record a heartbeat of `node` at the current time
+    pub fn heartbeat(&mut self, node: &T) {
+        let now = self.clock.now();
+        let max_samples = self.config.max_samples.max(1);
+
+        match self.histories.iter_mut().find(|h| h.node == *node) {
+            Some(history) => {
+                let interval = now.saturating_sub(history.last).as_secs_f64() * 1000.0;
+
+                if history.intervals.len() >= max_samples {
+                    history.intervals.pop_front();
+                }
+                history.intervals.push_back(interval);
+                history.last = now;
+            }
+            None => self.histories.push(History {
+                node: node.clone(),
+                last: now,
+                intervals: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// forget all heartbeats of `node`, e.g. after it left the cluster
+    pub fn remove(&mut self, node: &T) {
+        self.histories.retain(|h| h.node != *node);
+    }
+
+    fn estimate(&self, history: &History<T>) -> (f64, f64) {
+        let min_std = self.config.min_std_deviation.as_secs_f64() * 1000.0;
+        let pause = self.config.acceptable_heartbeat_pause.as_secs_f64() * 1000.0;
+
+        if history.intervals.is_empty() {
+            let mean = self.config.first_heartbeat_estimate.as_secs_f64() * 1000.0;
+            return (mean + pause, (mean / 4.0).max(min_std));
+        }
+
+        let n = history.intervals.len() as f64;
+        let mean = history.intervals.iter().sum::<f64>() / n;
+        let variance = history
+            .intervals
+            .iter()
+            .map(|i| (i - mean) * (i - mean))
+            .sum::<f64>()
+            / n;
+
+        (mean + pause, variance.sqrt().max(min_std))
+    }
+}
+
+impl<T, C> FailureDetector<T> for PhiAccrualDetector<T, C>
+where
+    T: Clone + PartialEq,
+    C: Clock,
+{
+    fn phi(&self, node: &T) -> f64 {
+        let Some(history) = self.histories.iter().find(|h| h.node == *node) else {
+            return 0.0;
+        };
+
+        let elapsed = self.clock.now().saturating_sub(history.last).as_secs_f64() * 1000.0;
+        let (mean, std_deviation) = self.estimate(history);
+
+        phi(elapsed, mean, std_deviation)
+    }
+
+    fn is_available(&self, node: &T) -> bool {
+        self.phi(node) < self.config.threshold
+    }
+}
+
+// -log10 of the probability that a heartbeat arrives later than `elapsed`, using a logistic approximation of the
+// cumulative normal distribution
+fn phi(elapsed: f64, mean: f64, std_deviation: f64) -> f64 {
+    let y = (elapsed - mean) / std_deviation;
+    let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+
+    if elapsed > mean {
+        -(e / (1.0 + e)).log10()
+    } else {
+        -(1.0 - 1.0 / (1.0 + e)).log10()
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// returns all nodes responsible for `key` that are available according to `detector`, in the order of `get`
+    pub fn get_healthy<U, D>(&self, key: &U, detector: &D) -> Vec<T>
+    where
+        U: Hash,
+        D: FailureDetector<T>,
+    {
+        self.get(key)
+            .into_iter()
+            .filter(|node| detector.is_available(node))
+            .collect()
+    }
+
+    /// returns all nodes responsible for `key` ordered by their suspicion level (lowest phi first)
+    ///
+    /// Nodes with equal phi keep the order of `get`.
+    pub fn get_by_suspicion<U, D>(&self, key: &U, detector: &D) -> Vec<T>
+    where
+        U: Hash,
+        D: FailureDetector<T>,
+    {
+        let mut nodes: Vec<(f64, T)> = self
+            .get(key)
+            .into_iter()
+            .map(|node| (detector.phi(&node), node))
+            .collect();
+
+        nodes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        nodes.into_iter().map(|(_, node)| node).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn detector() -> (ManualClock, PhiAccrualDetector<&'static str, ManualClock>) {
+        let clock = ManualClock::new();
+        let detector = PhiAccrualDetector::new(clock.clone(), PhiAccrualConfig::default());
+
+        (clock, detector)
+    }
+
+    fn heartbeats(
+        clock: &ManualClock,
+        detector: &mut PhiAccrualDetector<&'static str, ManualClock>,
+        nodes: &[&'static str],
+        count: usize,
+    ) {
+        for _ in 0..count {
+            clock.advance(Duration::from_millis(1000));
+            for node in nodes {
+                detector.heartbeat(node);
+            }
+        }
+    }
+
+    #[test]
+    fn phi_grows_with_missing_heartbeats() {
+        let (clock, mut detector) = detector();
+        heartbeats(&clock, &mut detector, &["a"], 20);
+
+        assert!(detector.phi(&"a") < 1.0);
+        assert!(detector.is_available(&"a"));
+
+        let mut last = detector.phi(&"a");
+        for _ in 0..5 {
+            clock.advance(Duration::from_millis(500));
+            let phi = detector.phi(&"a");
+            assert!(phi > last, "{phi} > {last}");
+            last = phi;
+        }
+
+        clock.advance(Duration::from_secs(1));
+        assert!(!detector.is_available(&"a"));
+
+        detector.heartbeat(&"a");
+        assert!(detector.is_available(&"a"));
+    }
+
+    #[test]
+    fn unknown_nodes_are_available() {
+        let (_, mut detector) = detector();
+
+        assert_eq!(0.0, detector.phi(&"a"));
+        assert!(detector.is_available(&"a"));
+
+        detector.heartbeat(&"a");
+        detector.remove(&"a");
+        assert_eq!(0.0, detector.phi(&"a"));
+    }
+
+    #[test]
+    fn acceptable_pause_delays_suspicion() {
+        let clock = ManualClock::new();
+        let mut strict = PhiAccrualDetector::new(clock.clone(), PhiAccrualConfig::default());
+        let mut tolerant = PhiAccrualDetector::new(
+            clock.clone(),
+            PhiAccrualConfig {
+                acceptable_heartbeat_pause: Duration::from_secs(3),
+                ..PhiAccrualConfig::default()
+            },
+        );
+
+        for _ in 0..10 {
+            clock.advance(Duration::from_secs(1));
+            strict.heartbeat(&"a");
+            tolerant.heartbeat(&"a");
+        }
+        clock.advance(Duration::from_secs(3));
+
+        assert!(!strict.is_available(&"a"));
+        assert!(tolerant.is_available(&"a"));
+    }
+
+    #[test]
+    fn ring_skips_and_reorders_suspected_replicas() {
+        let mut ring = HashRing::new(2, 10);
+        ring.batch_add(vec!["a", "b", "c", "d"]);
+
+        let (clock, mut detector) = detector();
+        heartbeats(&clock, &mut detector, &["a", "b", "c", "d"], 10);
+
+        for key in 0..50 {
+            assert_eq!(ring.get(&key), ring.get_healthy(&key, &detector));
+            assert_eq!(ring.get(&key), ring.get_by_suspicion(&key, &detector));
+        }
+
+        heartbeats(&clock, &mut detector, &["a", "c", "d"], 5);
+
+        for key in 0..50 {
+            let nodes = ring.get(&key);
+            let healthy = ring.get_healthy(&key, &detector);
+
+            let expected: Vec<_> = nodes.iter().filter(|n| **n != "b").copied().collect();
+            assert_eq!(expected, healthy);
+
+            if nodes.contains(&"b") {
+                assert_eq!(Some(&"b"), ring.get_by_suspicion(&key, &detector).last());
+            }
+        }
+    }
+}
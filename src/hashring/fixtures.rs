@@ -0,0 +1,38 @@
+//! test fixtures shared by the tests of the hashring modules
+
+use std::fmt::{self, Display};
+
+use super::HashRing;
+use super::strategy::Datacenter;
+
+/// node located in a datacenter, for rings using `NetworkTopologyStrategy`
+#[derive(Debug, Clone, Hash, PartialEq)]
+pub(crate) struct DcNode {
+    pub name: &'static str,
+    pub dc: &'static str,
+}
+
+impl DcNode {
+    pub fn new(name: &'static str, dc: &'static str) -> Self {
+        DcNode { name, dc }
+    }
+}
+
+impl Datacenter for DcNode {
+    fn datacenter(&self) -> &str {
+        self.dc
+    }
+}
+
+impl Display for DcNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.dc)
+    }
+}
+
+/// ring with 10 virtual nodes per node
+pub(crate) fn ring(replicas: usize, nodes: &[&'static str]) -> HashRing<&'static str> {
+    let mut ring = HashRing::new(replicas, 10);
+    ring.batch_add(nodes.to_vec());
+    ring
+}
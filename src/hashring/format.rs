@@ -0,0 +1,212 @@
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+
+use super::coordinator::{Replicas, share, width};
+
+/// format of hash ranges, see `Replicas::format_range` and `format_table`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RangeFormat {
+    /// bounds as decimal numbers: `0..=4611686018427387903`
+    #[default]
+    Decimal,
+    /// bounds as zero padded hex numbers: `0x0000000000000000..=0x3fffffffffffffff`
+    Hex,
+    /// bounds as position on the ring, the end is exclusive: `0.00%..25.00%`
+    Percent,
+    /// number of hashes within the range and its share of the ring: `4611686018427387904 (25.00%)`
+    Width,
+}
+
+impl RangeFormat {
+    /// format the given hash range
+    pub fn format(&self, hash_range: &RangeInclusive<u64>) -> String {
+        let (start, end) = (*hash_range.start(), *hash_range.end());
+        let width = width(hash_range);
+
+        match self {
+            RangeFormat::Decimal => format!("{start}..={end}"),
+            RangeFormat::Hex => format!("{start:#018x}..={end:#018x}"),
+            RangeFormat::Percent => format!(
+                "{:.2}%..{:.2}%",
+                percent(start as u128),
+                percent(end as u128 + 1)
+            ),
+            RangeFormat::Width => format!("{width} ({:.2}%)", percent(width)),
+        }
+    }
+}
+
+fn percent(hashes: u128) -> f64 {
+    share(hashes) * 100.0
+}
+
+impl<T> Replicas<T> {
+    /// format the hash range of these replicas
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{RangeFormat, Replicas};
+    ///
+    /// let replicas = Replicas {
+    ///     hash_range: 0..=u64::MAX / 4,
+    ///     nodes: vec!["node1"],
+    /// };
+    ///
+    /// assert_eq!("0.00%..25.00%", replicas.format_range(RangeFormat::Percent));
+    /// assert_eq!("0x0000000000000000..=0x3fffffffffffffff", replicas.format_range(RangeFormat::Hex));
+    /// ```
+    pub fn format_range(&self, format: RangeFormat) -> String {
+        format.format(&self.hash_range)
+    }
+}
+
+/// prints the hash range and all nodes, e.g. `0..=9 [node1, node2]`
+///
+/// The alternate flag (`{:#}`) prints the hash range as hex.
+impl<T: Display> Display for Replicas<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match f.alternate() {
+            true => RangeFormat::Hex,
+            false => RangeFormat::Decimal,
+        };
+
+        write!(f, "{} [", format.format(&self.hash_range))?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{node}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// format replicas as table with the columns hash range, share of the ring and nodes
+///
+/// # Arguments
+///
+/// * `replicas` - hash ranges to print, e.g. as returned by `get_hash_ranges` or `find_sources`
+/// * `format` - format of the hash range column
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::{HashRing, RangeFormat, format_table};
+///
+/// let mut ring = HashRing::new(1, 2);
+/// ring.batch_add(vec!["node1", "node2"]);
+///
+/// let table = format_table(&ring.get_hash_ranges(), RangeFormat::Hex);
+///
+/// assert!(table.starts_with("HASH RANGE"));
+/// println!("{table}");
+/// ```
+pub fn format_table<T: Display>(replicas: &[Replicas<T>], format: RangeFormat) -> String {
+    let rows: Vec<[String; 3]> = replicas
+        .iter()
+        .map(|replicas| {
+            [
+                format.format(&replicas.hash_range),
+                format!("{:.2}%", percent(replicas.width())),
+                join(&replicas.nodes),
+            ]
+        })
+        .collect();
+
+    let header = ["HASH RANGE", "SHARE", "NODES"];
+    let range_width = rows
+        .iter()
+        .map(|row| row[0].len())
+        .chain([header[0].len()])
+        .max()
+        .unwrap_or(0);
+    let share_width = rows
+        .iter()
+        .map(|row| row[1].len())
+        .chain([header[1].len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut table = String::new();
+    for row in std::iter::once(header.map(String::from)).chain(rows) {
+        let line = format!(
+            "{:<range_width$}  {:>share_width$}  {}",
+            row[0], row[1], row[2]
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+
+    table
+}
+
+/// nodes separated by comma, e.g. `node1, node2`
+pub(crate) fn join<T: Display>(nodes: &[T]) -> String {
+    nodes
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn range_formats() {
+        let range = u64::MAX / 2 + 1..=u64::MAX;
+
+        assert_eq!(
+            "9223372036854775808..=18446744073709551615",
+            RangeFormat::Decimal.format(&range)
+        );
+        assert_eq!(
+            "0x8000000000000000..=0xffffffffffffffff",
+            RangeFormat::Hex.format(&range)
+        );
+        assert_eq!("50.00%..100.00%", RangeFormat::Percent.format(&range));
+        assert_eq!(
+            "9223372036854775808 (50.00%)",
+            RangeFormat::Width.format(&range)
+        );
+        assert_eq!("1 (0.00%)", RangeFormat::Width.format(&(7..=7)));
+    }
+
+    #[test]
+    fn display_replicas() {
+        let replicas = Replicas {
+            hash_range: 0..=255,
+            nodes: vec!["a", "b"],
+        };
+
+        assert_eq!("0..=255 [a, b]", replicas.to_string());
+        assert_eq!(
+            "0x0000000000000000..=0x00000000000000ff [a, b]",
+            format!("{replicas:#}")
+        );
+    }
+
+    #[test]
+    fn table_is_aligned() {
+        let replicas = vec![
+            Replicas {
+                hash_range: 0..=u64::MAX / 4,
+                nodes: vec!["a", "b"],
+            },
+            Replicas {
+                hash_range: u64::MAX / 4 + 1..=u64::MAX,
+                nodes: vec![],
+            },
+        ];
+
+        assert_eq!(
+            "HASH RANGE        SHARE  NODES\n\
+             0.00%..25.00%    25.00%  a, b\n\
+             25.00%..100.00%  75.00%\n",
+            format_table(&replicas, RangeFormat::Percent)
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::HashRing;
+use super::strategy::ReplicationStrategy;
+
+/// a key that was written to `stand_in`, because its intended `owner` was down
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Hint<K, T> {
+    pub key: K,
+    pub owner: T,
+    pub stand_in: T,
+}
+
+/// keys that need to be handed back from `stand_in` to `owner`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Handoff<K, T> {
+    pub stand_in: T,
+    pub owner: T,
+    pub keys: Vec<K>,
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// returns all nodes that should store `key` right now as (stand-in, intended owner) pairs (sloppy quorum)
+    ///
+    /// Owners returned by `get` that are up store the key themselves (stand-in == owner). Each owner that is down
+    /// is replaced by the next healthy node clockwise on the ring, which is not an owner of this key already.
+    /// Owners without stand-in (all other nodes are down or owners themselves) are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key to write
+    /// * `is_up` - returns true if the given node is healthy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+    ///
+    /// let owners = ring.get(&"foo");
+    /// let down = owners[0];
+    ///
+    /// let nodes = ring.get_available(&"foo", |node| *node != down);
+    ///
+    /// assert_eq!(2, nodes.len());
+    /// assert_eq!(down, nodes[0].1);
+    /// assert_ne!(down, nodes[0].0);
+    /// assert_eq!((owners[1], owners[1]), nodes[1]);
+    /// ```
+    pub fn get_available<U, F>(&self, key: &U, is_up: F) -> Vec<(T, T)>
+    where
+        U: Hash,
+        F: Fn(&T) -> bool,
+    {
+        let owners = self.get(key);
+        if owners.is_empty() {
+            return vec![];
+        }
+
+        let n = self.position(self.get_hash(key));
+        let mut stand_ins = self.ring[n..]
+            .iter()
+            .chain(self.ring[..n].iter())
+            .map(|vnode| &vnode.node)
+            .filter(|node| !owners.contains(node) && is_up(node));
+
+        let mut used: Vec<&T> = vec![];
+        let mut nodes = vec![];
+
+        for owner in owners.iter() {
+            if is_up(owner) {
+                nodes.push((owner.clone(), owner.clone()));
+                continue;
+            }
+
+            if let Some(stand_in) = stand_ins.find(|node| !used.contains(node)) {
+                used.push(stand_in);
+                nodes.push((stand_in.clone(), owner.clone()));
+            }
+        }
+
+        nodes
+    }
+
+    /// same as `get_available`, but returns the hints stand-in nodes need to store along with `key`
+    pub fn hints<U, F>(&self, key: &U, is_up: F) -> Vec<Hint<U, T>>
+    where
+        U: Hash + Clone,
+        F: Fn(&T) -> bool,
+    {
+        self.get_available(key, is_up)
+            .into_iter()
+            .filter(|(stand_in, owner)| stand_in != owner)
+            .map(|(stand_in, owner)| Hint {
+                key: key.clone(),
+                owner,
+                stand_in,
+            })
+            .collect()
+    }
+
+    /// returns all keys that need to be handed back to `owner` once it is up again, grouped by stand-in node
+    ///
+    /// Hints of keys that `owner` is no longer responsible for (according to this HashRing) are skipped,
+    /// those keys are replicated by rebalancing the cluster instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `hints` - hints stored by all stand-in nodes
+    /// * `owner` - node that is up again
+    pub fn handoff<K>(&self, hints: &[Hint<K, T>], owner: &T) -> Vec<Handoff<K, T>>
+    where
+        K: Hash + Clone,
+    {
+        let mut handoffs: Vec<Handoff<K, T>> = vec![];
+
+        for hint in hints.iter() {
+            if hint.owner != *owner || !self.get(&hint.key).contains(owner) {
+                continue;
+            }
+
+            match handoffs
+                .iter_mut()
+                .find(|handoff| handoff.stand_in == hint.stand_in)
+            {
+                Some(handoff) => handoff.keys.push(hint.key.clone()),
+                None => handoffs.push(Handoff {
+                    stand_in: hint.stand_in.clone(),
+                    owner: owner.clone(),
+                    keys: vec![hint.key.clone()],
+                }),
+            }
+        }
+
+        handoffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashring::fixtures::ring;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn get_available_equals_get_if_all_nodes_are_up() {
+        let ring = ring(2, &["a", "b", "c", "d", "e", "f"]);
+
+        for key in 0..100 {
+            let expected: Vec<_> = ring.get(&key).into_iter().map(|n| (n, n)).collect();
+            assert_eq!(expected, ring.get_available(&key, |_| true));
+        }
+    }
+
+    #[test]
+    fn get_available_replaces_down_nodes() {
+        let ring = ring(2, &["a", "b", "c", "d", "e", "f"]);
+
+        for key in 0..100 {
+            let owners = ring.get(&key);
+            let down = [owners[0], owners[2]];
+
+            let nodes = ring.get_available(&key, |node| !down.contains(node));
+
+            assert_eq!(3, nodes.len());
+            assert_eq!((owners[1], owners[1]), nodes[1]);
+            assert_eq!(owners[0], nodes[0].1);
+            assert_eq!(owners[2], nodes[2].1);
+
+            for (stand_in, _) in [nodes[0], nodes[2]] {
+                assert!(!owners.contains(&stand_in));
+            }
+            assert_ne!(nodes[0].0, nodes[2].0);
+        }
+    }
+
+    #[test]
+    fn get_available_skips_owners_without_stand_in() {
+        let ring = ring(2, &["a", "b", "c", "d", "e", "f"]);
+
+        let owned = (0..).find(|key| ring.get(key).contains(&"a")).unwrap();
+        let not_owned = (0..).find(|key| !ring.get(key).contains(&"a")).unwrap();
+
+        // "a" is the only node up: it stores its own copy, the other owners have no stand-in
+        let nodes = ring.get_available(&owned, |node| *node == "a");
+        assert_eq!(vec![("a", "a")], nodes);
+
+        // "a" stands in for the first owner, the other owners have no stand-in
+        let owners = ring.get(&not_owned);
+        let nodes = ring.get_available(&not_owned, |node| *node == "a");
+        assert_eq!(vec![("a", owners[0])], nodes);
+    }
+
+    #[test]
+    fn handoff_groups_hints_by_stand_in() {
+        let ring = ring(2, &["a", "b", "c", "d", "e", "f"]);
+
+        let mut hints = vec![];
+        for key in 0..100 {
+            hints.extend(ring.hints(&key, |node| *node != "c"));
+        }
+        assert!(!hints.is_empty());
+
+        let handoffs = ring.handoff(&hints, &"c");
+
+        assert_eq!(
+            hints.len(),
+            handoffs.iter().map(|h| h.keys.len()).sum::<usize>()
+        );
+        for handoff in handoffs.iter() {
+            assert_eq!("c", handoff.owner);
+            for key in handoff.keys.iter() {
+                assert!(hints.contains(&Hint {
+                    key: *key,
+                    owner: "c",
+                    stand_in: handoff.stand_in
+                }));
+            }
+        }
+
+        let mut ring_new = ring.clone();
+        ring_new.remove(&"c");
+        ring_new.add("g");
+        assert!(
+            ring_new.handoff(&hints, &"c").is_empty(),
+            "c is no longer responsible for any key"
+        );
+    }
+}
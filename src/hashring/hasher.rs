@@ -0,0 +1,204 @@
+//! optional `BuildHasher` implementations to place keys like other systems do
+//!
+//! each hasher is only compiled if the matching cargo feature is enabled:
+//!
+//! * `xxhash` - [`Xxh3HashBuilder`] (xxHash3, 64 bit)
+//! * `murmur3` - [`Murmur3HashBuilder`] (MurmurHash3 x64_128, first 64 bit half)
+//! * `fnv` - [`FnvHashBuilder`] (FNV-1a, 64 bit)
+//!
+//! Keep in mind that `HashRing` hashes the `Hash` encoding of a key, e.g. `str` appends a `0xff` byte.
+//! To reproduce hashes of another system, your key type needs to feed exactly the same bytes into the hasher.
+
+#[cfg(any(feature = "xxhash", feature = "murmur3", feature = "fnv"))]
+use std::hash::BuildHasher;
+#[cfg(feature = "murmur3")]
+use std::hash::Hasher;
+
+#[cfg(any(feature = "xxhash", feature = "murmur3", feature = "fnv"))]
+use super::HashRing;
+
+/// BuildHasher for xxHash3 (64 bit) with the default seed
+#[cfg(feature = "xxhash")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Xxh3HashBuilder;
+
+#[cfg(feature = "xxhash")]
+impl BuildHasher for Xxh3HashBuilder {
+    type Hasher = xxhash_rust::xxh3::Xxh3Default;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        xxhash_rust::xxh3::Xxh3Default::new()
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl<T> HashRing<T, Xxh3HashBuilder> {
+    /// Create a new `HashRing` that places keys using xxHash3.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key (set replicas to 0, to store each key only once)
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    pub fn new_xxh3(replicas: usize, vnodes: usize) -> HashRing<T, Xxh3HashBuilder> {
+        HashRing::with_hasher(replicas, vnodes, Xxh3HashBuilder)
+    }
+}
+
+/// BuildHasher for MurmurHash3 x64_128 (seed 0)
+///
+/// The hash is the first 64 bit half of the 128 bit digest of the reference algorithm, with the sign bit flipped.
+/// Thus hashes are ordered on the ring like signed tokens (e.g. Cassandra's `Murmur3Partitioner` tokens).
+///
+/// Careful: Cassandra's implementation reads the trailing `len % 16` bytes as signed bytes. Its tokens differ from
+/// these hashes for keys with a trailing byte >= 0x80, so this is not a drop-in replacement for Cassandra's tokens.
+#[cfg(feature = "murmur3")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Murmur3HashBuilder;
+
+#[cfg(feature = "murmur3")]
+impl BuildHasher for Murmur3HashBuilder {
+    type Hasher = Murmur3Hasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        Murmur3Hasher { bytes: vec![] }
+    }
+}
+
+/// MurmurHash3 is not a streaming hash, so all written bytes are buffered until `finish` is called
+#[cfg(feature = "murmur3")]
+#[derive(Clone, Debug, Default)]
+pub struct Murmur3Hasher {
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "murmur3")]
+impl Hasher for Murmur3Hasher {
+    fn finish(&self) -> u64 {
+        let digest = murmur3::murmur3_x64_128(&mut self.bytes.as_slice(), 0)
+            .expect("reading from a slice cannot fail");
+
+        (digest as u64) ^ (1 << 63)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+#[cfg(feature = "murmur3")]
+impl<T> HashRing<T, Murmur3HashBuilder> {
+    /// Create a new `HashRing` that places keys using MurmurHash3.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key (set replicas to 0, to store each key only once)
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    pub fn new_murmur3(replicas: usize, vnodes: usize) -> HashRing<T, Murmur3HashBuilder> {
+        HashRing::with_hasher(replicas, vnodes, Murmur3HashBuilder)
+    }
+}
+
+/// BuildHasher for FNV-1a (64 bit)
+#[cfg(feature = "fnv")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct FnvHashBuilder;
+
+#[cfg(feature = "fnv")]
+impl BuildHasher for FnvHashBuilder {
+    type Hasher = fnv::FnvHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        fnv::FnvHasher::default()
+    }
+}
+
+#[cfg(feature = "fnv")]
+impl<T> HashRing<T, FnvHashBuilder> {
+    /// Create a new `HashRing` that places keys using FNV-1a.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key (set replicas to 0, to store each key only once)
+    /// * `vnodes` - number of virtual nodes per real node in the cluster
+    pub fn new_fnv(replicas: usize, vnodes: usize) -> HashRing<T, FnvHashBuilder> {
+        HashRing::with_hasher(replicas, vnodes, FnvHashBuilder)
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "xxhash", feature = "murmur3", feature = "fnv"))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn xxh3_matches_reference_implementation() {
+        let ring: HashRing<&str, Xxh3HashBuilder> = HashRing::new_xxh3(1, 10);
+
+        let mut expected = xxhash_rust::xxh3::Xxh3Default::new();
+        expected.update(b"foo");
+
+        assert_eq!(expected.digest(), ring.hash_builder.hash_one(Raw(b"foo")));
+    }
+
+    #[cfg(feature = "murmur3")]
+    #[test]
+    fn murmur3_matches_reference_vectors() {
+        let ring: HashRing<&str, Murmur3HashBuilder> = HashRing::new_murmur3(1, 10);
+        let hash = |bytes| ring.hash_builder.hash_one(Raw(bytes));
+
+        // digests of MurmurHash3_x64_128 (seed 0), first half with flipped sign bit
+        assert_eq!(0x8000000000000000, hash(b""));
+        // e34bbc7bbc071b6c 7a433ca9c49a9347
+        assert_eq!(
+            0x634bbc7bbc071b6c,
+            hash(b"The quick brown fox jumps over the lazy dog")
+        );
+        assert_eq!(0x6271865701f54561, hash(b"foo"));
+        // tail bytes >= 0x80 are unsigned (Cassandra would return 0x9f25023e6693df35 ^ (1 << 63))
+        assert_eq!(0x0458ae450e91a325, hash(&[0xff, 0x80, 0x01]));
+    }
+
+    #[cfg(feature = "fnv")]
+    #[test]
+    fn fnv_matches_reference_implementation() {
+        let ring: HashRing<&str, FnvHashBuilder> = HashRing::new_fnv(1, 10);
+
+        // FNV-1a 64 of "foo"
+        assert_eq!(0xdcb27518fed9d577, ring.hash_builder.hash_one(Raw(b"foo")));
+    }
+
+    #[test]
+    fn alternative_hashers_distribute_keys_across_all_nodes() {
+        #[cfg(feature = "xxhash")]
+        assert_all_nodes_used(HashRing::new_xxh3(0, 50));
+        #[cfg(feature = "murmur3")]
+        assert_all_nodes_used(HashRing::new_murmur3(0, 50));
+        #[cfg(feature = "fnv")]
+        assert_all_nodes_used(HashRing::new_fnv(0, 50));
+    }
+
+    fn assert_all_nodes_used<S: BuildHasher>(mut ring: HashRing<&str, S>) {
+        ring.batch_add(vec!["node1", "node2", "node3"]);
+
+        let mut used = vec![];
+        for key in 0..1000 {
+            for node in ring.get(&key) {
+                if !used.contains(&node) {
+                    used.push(node);
+                }
+            }
+        }
+
+        assert_eq!(3, used.len());
+    }
+
+    // writes the given bytes without a length prefix or terminator
+    struct Raw(&'static [u8]);
+
+    impl std::hash::Hash for Raw {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            state.write(self.0);
+        }
+    }
+}
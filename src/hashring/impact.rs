@@ -0,0 +1,296 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::HashRing;
+use super::coordinator::{Replicas, intersect, share, width};
+use super::strategy::ReplicationStrategy;
+
+/// share of the hash space a node receives, sends and gives up during a topology change (between 0.0 and 1.0)
+///
+/// * `incoming` - share of the hash space the node needs to receive
+/// * `outgoing` - share of the hash space the node sends to new owners as (preferred) donor
+/// * `released` - share of the hash space the node does not own anymore
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct NodeImpact<T> {
+    pub node: T,
+    pub incoming: f64,
+    pub outgoing: f64,
+    pub released: f64,
+}
+
+// hashes per node, converted to shares of the hash space once all ranges were visited
+struct Totals<T> {
+    node: T,
+    incoming: u128,
+    outgoing: u128,
+    released: u128,
+}
+
+/// impact of a topology change, as returned by `HashRing::impact`
+///
+/// * `moved` - share of the hash space (between 0.0 and 1.0) whose owners change
+/// * `transfers` - number of hash ranges that need to be copied to a new owner, adjacent hash ranges with the same
+///   target and donors count once (as `find_sources` merges them)
+/// * `nodes` - impact per node, for all nodes of both rings (nodes of the current ring first)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Impact<T> {
+    pub moved: f64,
+    pub transfers: usize,
+    pub nodes: Vec<NodeImpact<T>>,
+}
+
+impl<T> Impact<T> {
+    /// returns the node receiving the largest share of the hash space, None if nothing moves
+    pub fn peak_incoming(&self) -> Option<&NodeImpact<T>> {
+        self.nodes
+            .iter()
+            .filter(|n| n.incoming > 0.0)
+            .max_by(|a, b| a.incoming.total_cmp(&b.incoming))
+    }
+
+    /// returns the node sending the largest share of the hash space, None if nothing moves
+    pub fn peak_outgoing(&self) -> Option<&NodeImpact<T>> {
+        self.nodes
+            .iter()
+            .filter(|n| n.outgoing > 0.0)
+            .max_by(|a, b| a.outgoing.total_cmp(&b.outgoing))
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// returns the impact of changing the topology from this ring to `proposed`, without changing either ring
+    ///
+    /// Each hash range a node receives is sent by one donor: the first of its current owners that is still part of
+    /// `proposed` (ranked by the strategy of `proposed`, as `find_sources` would pick it). If no current owner is
+    /// part of `proposed` (e.g. the cluster is replaced), the current owners still send the hash range.
+    ///
+    /// # Arguments
+    ///
+    /// * `proposed` - ring after the topology change, it needs to use the same hash function
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3"]);
+    ///
+    /// let mut proposed = ring.clone();
+    /// proposed.add("node4");
+    ///
+    /// let impact = ring.impact(&proposed);
+    ///
+    /// assert!(impact.moved > 0.0);
+    /// assert_eq!("node4", impact.peak_incoming().unwrap().node);
+    /// ```
+    pub fn impact<S2, R2>(&self, proposed: &HashRing<T, S2, R2>) -> Impact<T>
+    where
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+    {
+        let remaining = proposed.nodes();
+
+        let mut nodes: Vec<Totals<T>> = vec![];
+        for node in self.nodes().into_iter().chain(remaining.iter().cloned()) {
+            if !nodes.iter().any(|n| n.node == node) {
+                nodes.push(Totals {
+                    node,
+                    incoming: 0,
+                    outgoing: 0,
+                    released: 0,
+                });
+            }
+        }
+
+        let mut moved = 0;
+        let mut plans: Vec<(T, Vec<Replicas<T>>)> = vec![];
+
+        let from = self.get_hash_ranges();
+        let to = proposed.get_hash_ranges();
+
+        for current in from.iter() {
+            let mut sources: Vec<T> = current
+                .nodes
+                .iter()
+                .filter(|n| remaining.contains(n))
+                .cloned()
+                .collect();
+            if sources.is_empty() {
+                sources = current.nodes.clone();
+            }
+
+            for next in to.iter() {
+                let Some(range) = intersect(&current.hash_range, &next.hash_range) else {
+                    continue;
+                };
+                let width = width(&range);
+                let mut changed = false;
+
+                for target in next.nodes.iter() {
+                    if current.nodes.contains(target) {
+                        continue;
+                    }
+
+                    let mut donors = sources.clone();
+                    proposed.strategy.rank_sources(target, &mut donors);
+
+                    for totals in nodes.iter_mut() {
+                        if totals.node == *target {
+                            totals.incoming += width;
+                        }
+                        if donors.first() == Some(&totals.node) {
+                            totals.outgoing += width;
+                        }
+                    }
+
+                    let replicas = Replicas {
+                        hash_range: range.clone(),
+                        nodes: donors,
+                    };
+                    match plans.iter_mut().find(|(node, _)| node == target) {
+                        Some((_, plan)) => plan.push(replicas),
+                        None => plans.push((target.clone(), vec![replicas])),
+                    }
+
+                    changed = true;
+                }
+
+                for totals in nodes.iter_mut() {
+                    if current.nodes.contains(&totals.node) && !next.nodes.contains(&totals.node) {
+                        totals.released += width;
+                        changed = true;
+                    }
+                }
+
+                if changed {
+                    moved += width;
+                }
+            }
+        }
+
+        let transfers = plans
+            .into_iter()
+            .map(|(_, plan)| proposed.merge_replicas(plan).len())
+            .sum();
+
+        Impact {
+            moved: share(moved),
+            transfers,
+            nodes: nodes
+                .into_iter()
+                .map(|totals| NodeImpact {
+                    node: totals.node,
+                    incoming: share(totals.incoming),
+                    outgoing: share(totals.outgoing),
+                    released: share(totals.released),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::fixtures::ring;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unchanged_ring_has_no_impact() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let impact = ring.impact(&ring.clone());
+
+        assert_eq!(0.0, impact.moved);
+        assert_eq!(0, impact.transfers);
+        assert_eq!(4, impact.nodes.len());
+        assert!(impact.peak_incoming().is_none());
+        assert!(impact.peak_outgoing().is_none());
+    }
+
+    #[test]
+    fn adding_a_node_moves_hash_space_to_it() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let mut proposed = ring.clone();
+        proposed.add("e");
+
+        let impact = ring.impact(&proposed);
+
+        let e = impact.nodes.iter().find(|n| n.node == "e").unwrap();
+        assert_eq!(0.0, e.outgoing);
+        assert_eq!(0.0, e.released);
+        assert_eq!(e, impact.peak_incoming().unwrap());
+
+        // only the new node receives data, the old nodes send and give up the same share
+        let incoming: f64 = impact.nodes.iter().map(|n| n.incoming).sum();
+        let outgoing: f64 = impact.nodes.iter().map(|n| n.outgoing).sum();
+        let released: f64 = impact.nodes.iter().map(|n| n.released).sum();
+        assert!((incoming - e.incoming).abs() < 1e-9);
+        assert!((incoming - outgoing).abs() < 1e-9);
+        assert!((incoming - released).abs() < 1e-9);
+        assert!(impact.moved <= incoming + 1e-9);
+
+        let planned: usize = proposed
+            .nodes()
+            .iter()
+            .map(|target| proposed.find_sources(target, &ring, &ring.nodes()).len())
+            .sum();
+        assert!(impact.transfers > 0);
+        assert_eq!(planned, impact.transfers);
+    }
+
+    #[test]
+    fn removed_node_releases_but_does_not_donate() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let mut proposed = ring.clone();
+        proposed.remove(&"d");
+
+        let removal = ring.impact(&proposed);
+        let addition = proposed.impact(&ring);
+
+        assert_eq!(removal.moved, addition.moved);
+
+        let d = removal.nodes.iter().find(|n| n.node == "d").unwrap();
+        assert_eq!(0.0, d.outgoing);
+        assert_eq!(0.0, d.incoming);
+        assert!(d.released > 0.0);
+        assert_ne!("d", removal.peak_outgoing().unwrap().node);
+
+        // the surviving owners send everything that is received
+        let incoming: f64 = removal.nodes.iter().map(|n| n.incoming).sum();
+        let outgoing: f64 = removal.nodes.iter().map(|n| n.outgoing).sum();
+        assert!((incoming - outgoing).abs() < 1e-9);
+
+        for node in removal.nodes.iter() {
+            let inverse = addition.nodes.iter().find(|n| n.node == node.node).unwrap();
+            assert_eq!(node.incoming, inverse.released);
+            assert_eq!(node.released, inverse.incoming);
+        }
+    }
+
+    #[test]
+    fn replaced_cluster_donates_from_current_owners() {
+        let current = ring(0, &["a", "b"]);
+        let proposed = ring(0, &["c", "d"]);
+
+        let impact = current.impact(&proposed);
+
+        let outgoing: f64 = impact
+            .nodes
+            .iter()
+            .filter(|n| n.node == "a" || n.node == "b")
+            .map(|n| n.outgoing)
+            .sum();
+        assert!((1.0 - outgoing).abs() < 1e-9);
+        assert!((1.0 - impact.moved).abs() < 1e-9);
+    }
+}
@@ -12,7 +12,7 @@ impl<T> Iterator for HashRingIterator<T> {
     }
 }
 
-impl<T> IntoIterator for HashRing<T> {
+impl<T, S, R> IntoIterator for HashRing<T, S, R> {
     type Item = T;
 
     type IntoIter = HashRingIterator<T>;
@@ -24,7 +24,7 @@ impl<T> IntoIterator for HashRing<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a HashRing<T> {
+impl<'a, T, S, R> IntoIterator for &'a HashRing<T, S, R> {
     type Item = &'a T;
     type IntoIter = HashRingRefIterator<'a, T>;
 
@@ -0,0 +1,301 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::coordinator::Replicas;
+use super::strategy::ReplicationStrategy;
+use super::{DefaultHashBuilder, HashRing};
+
+/// MerkleTree summarizes all keys stored within each hash range to cheaply detect replicas that differ
+///
+/// Each leaf covers one hash range and stores the (wrapping) sum of the digests of all its keys, thus keys can be
+/// added and removed in any order (incremental updates). Each key/version needs to be inserted exactly once: a key
+/// inserted twice counts twice, remove the old digest before inserting the digest of a new value. Two trees built from the same hash ranges can be compared
+/// with `diff`, which only descends into subtrees with different digests.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct MerkleTree<T> {
+    leaves: Vec<Replicas<T>>,
+    // binary tree in heap layout: root at index 1, leaves start at index `leaves.len().next_power_of_two()`
+    digests: Vec<u64>,
+}
+
+impl<T> MerkleTree<T>
+where
+    T: Clone,
+{
+    /// Create a new, empty `MerkleTree`
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_ranges` - one leaf per hash range, e.g. as returned by `get_hash_ranges` (or split into smaller ranges)
+    pub fn new(mut hash_ranges: Vec<Replicas<T>>) -> Self {
+        hash_ranges.sort_by_key(|replicas| *replicas.hash_range.start());
+
+        let size = hash_ranges.len().next_power_of_two();
+
+        MerkleTree {
+            leaves: hash_ranges,
+            digests: vec![0; 2 * size],
+        }
+    }
+
+    /// add a key to the tree, the same key/version must not be added twice
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - position of the key on the ring, as returned by `HashRing::get_hash`
+    /// * `digest` - digest of the key and its value (or version), see `merkle_digest`
+    pub fn insert(&mut self, hash: u64, digest: u64) {
+        self.update(hash, |leaf| leaf.wrapping_add(digest));
+    }
+
+    /// remove a key that was added with the same `hash` and `digest` before
+    pub fn remove(&mut self, hash: u64, digest: u64) {
+        self.update(hash, |leaf| leaf.wrapping_sub(digest));
+    }
+
+    // updates the leaf digest of `hash` and all digests up to the root
+    fn update<F: FnOnce(u64) -> u64>(&mut self, hash: u64, f: F) {
+        let Some(leaf) = self.leaf(hash) else {
+            return;
+        };
+
+        let mut i = self.size() + leaf;
+        self.digests[i] = f(self.digests[i]);
+
+        while i > 1 {
+            i /= 2;
+            self.digests[i] = combine(self.digests[2 * i], self.digests[2 * i + 1]);
+        }
+    }
+
+    /// digest of all keys in this tree
+    pub fn root(&self) -> u64 {
+        self.digests[1]
+    }
+
+    /// returns all leaves (hash ranges with their nodes) and their digest
+    pub fn leaves(&self) -> impl Iterator<Item = (&Replicas<T>, u64)> {
+        let size = self.size();
+
+        self.leaves
+            .iter()
+            .enumerate()
+            .map(move |(i, replicas)| (replicas, self.digests[size + i]))
+    }
+
+    /// returns all hash ranges that differ between both trees, with the nodes of this tree to repair them
+    ///
+    /// Returns None if both trees do not consist of the same hash ranges and thus cannot be compared.
+    pub fn diff(&self, other: &MerkleTree<T>) -> Option<Vec<Replicas<T>>> {
+        let same_ranges = self.leaves.len() == other.leaves.len()
+            && self
+                .leaves
+                .iter()
+                .zip(other.leaves.iter())
+                .all(|(a, b)| a.hash_range == b.hash_range);
+
+        if !same_ranges {
+            return None;
+        }
+
+        let size = self.size();
+        let mut differences = vec![];
+        let mut stack = vec![1];
+
+        while let Some(i) = stack.pop() {
+            if self.digests[i] == other.digests[i] {
+                continue;
+            }
+
+            if i >= size {
+                differences.push(self.leaves[i - size].clone());
+            } else {
+                stack.push(2 * i + 1);
+                stack.push(2 * i);
+            }
+        }
+
+        Some(differences)
+    }
+
+    fn size(&self) -> usize {
+        self.digests.len() / 2
+    }
+
+    fn leaf(&self, hash: u64) -> Option<usize> {
+        let i = self
+            .leaves
+            .partition_point(|replicas| *replicas.hash_range.start() <= hash)
+            .checked_sub(1)?;
+
+        self.leaves[i].hash_range.contains(&hash).then_some(i)
+    }
+}
+
+/// returns the digest of a key and its value to add it to a `MerkleTree`
+pub fn merkle_digest<K: Hash, V: Hash>(key: &K, value: &V) -> u64 {
+    DefaultHashBuilder.hash_one((key, value))
+}
+
+// subtrees without keys keep the digest 0, thus empty parts of both trees are equal without hashing
+fn combine(left: u64, right: u64) -> u64 {
+    if left == 0 && right == 0 {
+        return 0;
+    }
+
+    DefaultHashBuilder.hash_one((left, right))
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// Create an empty `MerkleTree` with one leaf per hash range of `get_hash_ranges`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, merkle_digest};
+    ///
+    /// let mut ring = HashRing::new(1, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3"]);
+    ///
+    /// let mut local = ring.merkle_tree();
+    /// let mut remote = ring.merkle_tree();
+    ///
+    /// for (key, value) in [("foo", 1), ("bar", 2)] {
+    ///     local.insert(ring.get_hash(&key), merkle_digest(&key, &value));
+    ///     remote.insert(ring.get_hash(&key), merkle_digest(&key, &value));
+    /// }
+    /// remote.insert(ring.get_hash(&"baz"), merkle_digest(&"baz", &3));
+    ///
+    /// let repair = local.diff(&remote).unwrap();
+    ///
+    /// assert_eq!(1, repair.len());
+    /// assert!(repair[0].hash_range.contains(&ring.get_hash(&"baz")));
+    /// ```
+    pub fn merkle_tree(&self) -> MerkleTree<T> {
+        MerkleTree::new(self.get_hash_ranges())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashring::fixtures::ring;
+    use pretty_assertions::assert_eq;
+
+    fn fill(ring: &HashRing<&'static str>, tree: &mut MerkleTree<&'static str>, keys: &[u32]) {
+        for key in keys {
+            tree.insert(ring.get_hash(key), merkle_digest(key, &"value"));
+        }
+    }
+
+    #[test]
+    fn leaves_align_with_hash_ranges() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let tree = ring.merkle_tree();
+
+        let mut ranges = ring.get_hash_ranges();
+        ranges.sort_by_key(|r| *r.hash_range.start());
+
+        assert_eq!(
+            ranges,
+            tree.leaves().map(|(r, _)| r.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(0, tree.root());
+    }
+
+    #[test]
+    fn insert_is_independent_of_order_and_can_be_undone() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let keys: Vec<u32> = (0..200).collect();
+        let reversed: Vec<u32> = keys.iter().rev().copied().collect();
+
+        let mut a = ring.merkle_tree();
+        let mut b = ring.merkle_tree();
+        fill(&ring, &mut a, &keys);
+        fill(&ring, &mut b, &reversed);
+
+        assert_ne!(0, a.root());
+        assert_eq!(a, b);
+
+        a.insert(ring.get_hash(&500), merkle_digest(&500, &"value"));
+        assert_ne!(a.root(), b.root());
+
+        a.remove(ring.get_hash(&500), merkle_digest(&500, &"value"));
+        assert_eq!(a.root(), b.root());
+    }
+
+    #[test]
+    fn diff_returns_differing_hash_ranges() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let keys: Vec<u32> = (0..200).collect();
+
+        let mut a = ring.merkle_tree();
+        let mut b = ring.merkle_tree();
+        fill(&ring, &mut a, &keys);
+        fill(&ring, &mut b, &keys);
+
+        assert_eq!(Some(vec![]), a.diff(&b));
+
+        b.insert(ring.get_hash(&7), merkle_digest(&7, &"changed"));
+        b.insert(ring.get_hash(&1000), merkle_digest(&1000, &"value"));
+
+        let mut diff = a.diff(&b).unwrap();
+        diff.sort_by_key(|r| *r.hash_range.start());
+
+        let mut expected: Vec<_> = a
+            .leaves()
+            .map(|(replicas, _)| replicas.clone())
+            .filter(|r| {
+                r.hash_range.contains(&ring.get_hash(&7))
+                    || r.hash_range.contains(&ring.get_hash(&1000))
+            })
+            .collect();
+        expected.sort_by_key(|r| *r.hash_range.start());
+
+        assert_eq!(expected, diff);
+        for hash in [ring.get_hash(&7), ring.get_hash(&1000)] {
+            let replicas = diff.iter().find(|r| r.hash_range.contains(&hash)).unwrap();
+            assert_eq!(ring.get_by_hash(hash), replicas.nodes);
+        }
+    }
+
+    #[test]
+    fn duplicate_insert_is_not_the_same_as_no_key() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+        let hash = ring.get_hash(&7);
+        let digest = merkle_digest(&7, &"value");
+
+        let empty = ring.merkle_tree();
+        let mut once = ring.merkle_tree();
+        once.insert(hash, digest);
+        let mut twice = once.clone();
+        twice.insert(hash, digest);
+
+        assert_ne!(empty.root(), twice.root());
+        assert_ne!(once.root(), twice.root());
+
+        twice.remove(hash, digest);
+        assert_eq!(once, twice);
+        twice.remove(hash, digest);
+        assert_eq!(empty, twice);
+    }
+
+    #[test]
+    fn diff_requires_same_hash_ranges() {
+        let ring = ring(1, &["a", "b", "c", "d"]);
+
+        let mut ring_new = ring.clone();
+        ring_new.add("e");
+
+        assert_eq!(None, ring.merkle_tree().diff(&ring_new.merkle_tree()));
+    }
+}
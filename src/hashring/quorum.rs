@@ -0,0 +1,358 @@
+use std::fmt::{self, Debug, Display};
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::HashRing;
+use super::strategy::ReplicationStrategy;
+
+/// error of a quorum read or write
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuorumError {
+    /// reads need to be between 1 and n
+    InvalidReads { reads: usize, n: usize },
+    /// writes need to be between 1 and n
+    InvalidWrites { writes: usize, n: usize },
+    /// reads + writes need to be greater than n, otherwise a read may miss the latest write
+    NoOverlap {
+        reads: usize,
+        writes: usize,
+        n: usize,
+    },
+    /// the HashRing returned fewer nodes for a key than required
+    NotEnoughNodes { required: usize, available: usize },
+    /// fewer nodes answered than required
+    NotEnoughResponses { required: usize, received: usize },
+}
+
+impl Display for QuorumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumError::InvalidReads { reads, n } => {
+                write!(f, "R = {reads} needs to be between 1 and N = {n}")
+            }
+            QuorumError::InvalidWrites { writes, n } => {
+                write!(f, "W = {writes} needs to be between 1 and N = {n}")
+            }
+            QuorumError::NoOverlap { reads, writes, n } => {
+                write!(
+                    f,
+                    "R + W = {} needs to be greater than N = {n}",
+                    reads + writes
+                )
+            }
+            QuorumError::NotEnoughNodes {
+                required,
+                available,
+            } => write!(
+                f,
+                "{required} nodes required, but only {available} available"
+            ),
+            QuorumError::NotEnoughResponses { required, received } => {
+                write!(
+                    f,
+                    "{required} responses required, but only {received} received"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuorumError {}
+
+/// ConflictResolver merges two responses of a quorum read into one
+///
+/// Any closure `Fn(V, V) -> V` can be used as ConflictResolver.
+pub trait ConflictResolver<V> {
+    /// merge two conflicting values
+    fn resolve(&self, a: V, b: V) -> V;
+}
+
+impl<V, F> ConflictResolver<V> for F
+where
+    F: Fn(V, V) -> V,
+{
+    fn resolve(&self, a: V, b: V) -> V {
+        self(a, b)
+    }
+}
+
+/// value with a version, e.g. a timestamp of the write
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Versioned<V> {
+    pub version: u64,
+    pub value: V,
+}
+
+/// resolves conflicts of `Versioned` values by keeping the highest version
+///
+/// If both versions are equal, the first value is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LastWriteWins;
+
+impl<V> ConflictResolver<Versioned<V>> for LastWriteWins {
+    fn resolve(&self, a: Versioned<V>, b: Versioned<V>) -> Versioned<V> {
+        if b.version > a.version { b } else { a }
+    }
+}
+
+/// Quorum defines how many of the N nodes storing a key need to answer a read (R) or acknowledge a write (W)
+///
+/// R + W > N guarantees that every read contacts at least one node with the latest write.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Quorum {
+    n: usize,
+    reads: usize,
+    writes: usize,
+}
+
+impl Quorum {
+    /// Create a new `Quorum`
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - number of nodes storing each key (replicas + 1)
+    /// * `reads` - number of nodes that need to answer a read (R)
+    /// * `writes` - number of nodes that need to acknowledge a write (W)
+    pub fn new(n: usize, reads: usize, writes: usize) -> Result<Self, QuorumError> {
+        if reads == 0 || reads > n {
+            return Err(QuorumError::InvalidReads { reads, n });
+        }
+        if writes == 0 || writes > n {
+            return Err(QuorumError::InvalidWrites { writes, n });
+        }
+        if reads + writes <= n {
+            return Err(QuorumError::NoOverlap { reads, writes, n });
+        }
+
+        Ok(Quorum { n, reads, writes })
+    }
+
+    /// number of nodes storing each key
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// number of nodes that need to answer a read
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    /// number of nodes that need to acknowledge a write
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    /// returns Ok if enough nodes acknowledged a write
+    pub fn check_writes(&self, acks: usize) -> Result<(), QuorumError> {
+        match acks >= self.writes {
+            true => Ok(()),
+            false => Err(QuorumError::NotEnoughResponses {
+                required: self.writes,
+                received: acks,
+            }),
+        }
+    }
+
+    /// merge all responses of a read with `resolver`, returns an error if fewer than R nodes answered
+    ///
+    /// # Arguments
+    ///
+    /// * `responses` - values returned by the contacted nodes
+    /// * `resolver` - implementation of ConflictResolver to merge conflicting values
+    pub fn resolve<V, C>(&self, responses: Vec<V>, resolver: &C) -> Result<V, QuorumError>
+    where
+        C: ConflictResolver<V>,
+    {
+        let received = responses.len();
+
+        if received < self.reads {
+            return Err(QuorumError::NotEnoughResponses {
+                required: self.reads,
+                received,
+            });
+        }
+
+        responses
+            .into_iter()
+            .reduce(|a, b| resolver.resolve(a, b))
+            .ok_or(QuorumError::NotEnoughResponses {
+                required: self.reads,
+                received,
+            })
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// Create a new `Quorum` for this HashRing with N = replicas + 1
+    ///
+    /// # Arguments
+    ///
+    /// * `reads` - number of nodes that need to answer a read (R)
+    /// * `writes` - number of nodes that need to acknowledge a write (W)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, LastWriteWins, Versioned};
+    ///
+    /// let mut ring = HashRing::new(2, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+    ///
+    /// let quorum = ring.quorum(2, 2).unwrap();
+    ///
+    /// let write_to = ring.write_targets(&"foo", &quorum).unwrap();
+    /// assert_eq!(3, write_to.len());
+    ///
+    /// let read_from = ring.read_targets(&"foo", &quorum).unwrap();
+    /// assert_eq!(2, read_from.len());
+    ///
+    /// let responses = vec![
+    ///     Versioned { version: 1, value: "old" },
+    ///     Versioned { version: 2, value: "new" },
+    /// ];
+    /// assert_eq!("new", quorum.resolve(responses, &LastWriteWins).unwrap().value);
+    /// ```
+    pub fn quorum(&self, reads: usize, writes: usize) -> Result<Quorum, QuorumError> {
+        Quorum::new(self.replicas + 1, reads, writes)
+    }
+
+    /// returns the first R nodes responsible for `key`, which need to be contacted for a read
+    ///
+    /// Returns an error if the HashRing contains fewer than R nodes.
+    pub fn read_targets<U: Hash>(&self, key: &U, quorum: &Quorum) -> Result<Vec<T>, QuorumError> {
+        let mut nodes = self.quorum_nodes(key, quorum.reads)?;
+        nodes.truncate(quorum.reads);
+
+        Ok(nodes)
+    }
+
+    /// returns all nodes responsible for `key`, a write succeeds once W of them acknowledged it
+    ///
+    /// Returns an error if the HashRing contains fewer than W nodes.
+    pub fn write_targets<U: Hash>(&self, key: &U, quorum: &Quorum) -> Result<Vec<T>, QuorumError> {
+        self.quorum_nodes(key, quorum.writes)
+    }
+
+    fn quorum_nodes<U: Hash>(&self, key: &U, required: usize) -> Result<Vec<T>, QuorumError> {
+        let nodes = self.get(key);
+
+        match nodes.len() >= required {
+            true => Ok(nodes),
+            false => Err(QuorumError::NotEnoughNodes {
+                required,
+                available: nodes.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn quorum_validates_reads_and_writes() {
+        assert!(Quorum::new(3, 2, 2).is_ok());
+        assert!(Quorum::new(3, 1, 3).is_ok());
+
+        assert_eq!(
+            Err(QuorumError::InvalidReads { reads: 0, n: 3 }),
+            Quorum::new(3, 0, 3)
+        );
+        assert_eq!(
+            Err(QuorumError::InvalidWrites { writes: 4, n: 3 }),
+            Quorum::new(3, 2, 4)
+        );
+        assert_eq!(
+            Err(QuorumError::NoOverlap {
+                reads: 1,
+                writes: 2,
+                n: 3
+            }),
+            Quorum::new(3, 1, 2)
+        );
+    }
+
+    #[test]
+    fn targets_are_taken_from_get() {
+        let mut ring = HashRing::new(2, 10);
+        ring.batch_add(vec!["a", "b", "c", "d"]);
+
+        let quorum = ring.quorum(2, 2).unwrap();
+        assert_eq!(3, quorum.n());
+
+        for key in 0..100 {
+            let nodes = ring.get(&key);
+
+            assert_eq!(nodes, ring.write_targets(&key, &quorum).unwrap());
+            assert_eq!(nodes[..2], ring.read_targets(&key, &quorum).unwrap());
+        }
+    }
+
+    #[test]
+    fn targets_fail_if_ring_is_too_small() {
+        let mut ring = HashRing::new(2, 10);
+        ring.add("a");
+
+        let quorum = ring.quorum(2, 2).unwrap();
+
+        assert_eq!(
+            Err(QuorumError::NotEnoughNodes {
+                required: 2,
+                available: 1
+            }),
+            ring.read_targets(&"foo", &quorum)
+        );
+    }
+
+    #[test]
+    fn resolve_merges_responses() {
+        let quorum = Quorum::new(3, 2, 2).unwrap();
+
+        let responses = vec![
+            Versioned {
+                version: 3,
+                value: "b",
+            },
+            Versioned {
+                version: 5,
+                value: "c",
+            },
+            Versioned {
+                version: 5,
+                value: "d",
+            },
+        ];
+        assert_eq!(
+            Ok(Versioned {
+                version: 5,
+                value: "c"
+            }),
+            quorum.resolve(responses, &LastWriteWins)
+        );
+
+        assert_eq!(
+            Ok(7),
+            quorum.resolve(vec![3, 7, 5], &|a: i32, b: i32| a.max(b))
+        );
+        assert_eq!(
+            Err(QuorumError::NotEnoughResponses {
+                required: 2,
+                received: 1
+            }),
+            quorum.resolve(vec![3], &|a: i32, b: i32| a.max(b))
+        );
+        assert!(quorum.check_writes(2).is_ok());
+        assert!(quorum.check_writes(1).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use std::f64::consts::PI;
+use std::fmt::{Debug, Display, Write};
+use std::hash::{BuildHasher, Hash};
+
+use super::HashRing;
+use super::coordinator::share;
+use super::format::join;
+use super::strategy::ReplicationStrategy;
+use crate::replication::migration::Migration;
+
+const SIZE: f64 = 400.0;
+const RADIUS: f64 = 150.0;
+const ARC_WIDTH: f64 = 30.0;
+const TICK: f64 = 22.0;
+// color of hash ranges without any node (e.g. no node in the datacenters of the strategy)
+const UNOWNED: &str = "lightgray";
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq + Display,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// render the ring as SVG chart
+    ///
+    /// Every hash range is drawn as arc in the color of its primary node (hash 0 at the top, clockwise), every
+    /// virtual node is drawn as tick mark. Hover an arc to see its hash range and all nodes storing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let mut ring = HashRing::new(1, 4);
+    /// ring.batch_add(vec!["node1", "node2", "node3"]);
+    ///
+    /// let svg = ring.to_svg();
+    ///
+    /// assert!(svg.starts_with("<svg"));
+    /// // std::fs::write("ring.svg", svg).unwrap();
+    /// ```
+    pub fn to_svg(&self) -> String {
+        let nodes = self.nodes();
+        let center = SIZE / 2.0;
+        let legend = nodes.len() as f64 * 20.0 + 20.0;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+            SIZE + 200.0,
+            SIZE.max(legend)
+        );
+
+        for replicas in self.get_hash_ranges() {
+            let color = match replicas.nodes.first() {
+                Some(primary) => color(nodes.iter().position(|n| n == primary).unwrap_or(0)),
+                None => UNOWNED.to_string(),
+            };
+            let owners = match replicas.nodes.is_empty() {
+                true => "unowned".to_string(),
+                false => join(&replicas.nodes),
+            };
+            let title = format!(
+                "{}..={}: {owners}",
+                replicas.hash_range.start(),
+                replicas.hash_range.end()
+            );
+
+            let start = share(*replicas.hash_range.start() as u128);
+            let end = share(*replicas.hash_range.end() as u128 + 1);
+
+            if end - start >= 1.0 {
+                let _ = writeln!(
+                    svg,
+                    r#"  <circle cx="{center}" cy="{center}" r="{RADIUS}" fill="none" stroke="{color}" stroke-width="{ARC_WIDTH}"><title>{}</title></circle>"#,
+                    escape(&title)
+                );
+                continue;
+            }
+
+            let (x1, y1) = point(start, RADIUS);
+            let (x2, y2) = point(end, RADIUS);
+            let large = if end - start > 0.5 { 1 } else { 0 };
+
+            let _ = writeln!(
+                svg,
+                r#"  <path d="M {x1:.3} {y1:.3} A {RADIUS} {RADIUS} 0 {large} 1 {x2:.3} {y2:.3}" fill="none" stroke="{color}" stroke-width="{ARC_WIDTH}"><title>{}</title></path>"#,
+                escape(&title)
+            );
+        }
+
+        for vnode in self.ring.iter() {
+            let position = share(vnode.key as u128);
+            let (x1, y1) = point(position, RADIUS - TICK);
+            let (x2, y2) = point(position, RADIUS + TICK);
+
+            let _ = writeln!(
+                svg,
+                r#"  <line x1="{x1:.3}" y1="{y1:.3}" x2="{x2:.3}" y2="{y2:.3}" stroke="black"><title>{} #{}</title></line>"#,
+                escape(&vnode.node.to_string()),
+                vnode.virtual_id
+            );
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let y = 20.0 + i as f64 * 20.0;
+            let _ = writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="12" height="12" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                SIZE + 10.0,
+                y - 10.0,
+                color(i),
+                SIZE + 30.0,
+                y,
+                escape(&node.to_string())
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl<T> Migration<T>
+where
+    T: Clone + PartialEq + Display,
+{
+    /// render all transfers of this migration as Graphviz DOT diagram
+    ///
+    /// Every edge points from the first source node to a target node and is labeled with the number of hash ranges
+    /// and the share of the hash space to copy. Edges are dashed once all their hash ranges are done. Hash ranges
+    /// without an available source node start at a node labeled `unavailable`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, Migration};
+    ///
+    /// let mut ring_original = HashRing::new(0, 10);
+    /// ring_original.batch_add(vec!["node1", "node2"]);
+    ///
+    /// let mut ring_new = ring_original.clone();
+    /// ring_new.add("node3");
+    ///
+    /// let migration = Migration::plan(&ring_new, &ring_original, &ring_original.nodes());
+    ///
+    /// let dot = migration.to_dot();
+    ///
+    /// assert!(dot.contains(r#"-> "node3""#));
+    /// // render with: dot -Tsvg migration.dot > migration.svg
+    /// ```
+    pub fn to_dot(&self) -> String {
+        // (source, target, ranges, width, done width)
+        let mut edges: Vec<(Option<&T>, &T, usize, u128, u128)> = vec![];
+
+        for transfer in self.transfers() {
+            let source = transfer.replicas.nodes.first();
+            let width = transfer.replicas.width();
+            let done = transfer.done_width();
+
+            match edges
+                .iter_mut()
+                .find(|(s, t, ..)| *s == source && **t == transfer.target)
+            {
+                Some(edge) => {
+                    edge.2 += 1;
+                    edge.3 += width;
+                    edge.4 += done;
+                }
+                None => edges.push((source, &transfer.target, 1, width, done)),
+            }
+        }
+
+        let mut dot = String::from("digraph migration {\n  rankdir=LR;\n  node [shape=box];\n");
+
+        if edges.iter().any(|(source, ..)| source.is_none()) {
+            dot.push_str("  unavailable [shape=octagon, color=red];\n");
+        }
+
+        for (source, target, ranges, width, done) in edges {
+            let source = match source {
+                Some(source) => quote(&source.to_string()),
+                None => "unavailable".to_string(),
+            };
+            let style = if done == width { ", style=dashed" } else { "" };
+
+            let _ = writeln!(
+                dot,
+                r#"  {source} -> {} [label="{ranges} ranges\n{:.2}%"{style}];"#,
+                quote(&target.to_string()),
+                share(width) * 100.0
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// point on the circle at the given share of the hash space, starting at the top (clockwise)
+fn point(fraction: f64, radius: f64) -> (f64, f64) {
+    let angle = fraction * 2.0 * PI;
+    let center = SIZE / 2.0;
+
+    (center + radius * angle.sin(), center - radius * angle.cos())
+}
+
+/// distinct colors for neighbouring indexes (golden angle)
+fn color(i: usize) -> String {
+    format!("hsl({:.0},65%,55%)", (i as f64 * 137.508) % 360.0)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashring::fixtures::DcNode;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn svg_contains_arcs_ticks_and_legend() {
+        let mut ring = HashRing::new(1, 4);
+        ring.batch_add(vec!["a", "b", "<c>"]);
+
+        let svg = ring.to_svg();
+
+        assert_eq!(ring.get_hash_ranges().len(), svg.matches("<path").count());
+        assert_eq!(ring.vlen(), svg.matches("<line").count());
+        assert_eq!(3, svg.matches("<rect").count());
+        assert!(svg.contains(">&lt;c&gt;</text>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn svg_of_single_node_is_a_circle() {
+        let mut ring = HashRing::new(0, 1);
+        ring.add("a");
+
+        let svg = ring.to_svg();
+
+        assert_eq!(0, svg.matches("<path").count());
+        assert_eq!(1, svg.matches("<circle").count());
+    }
+
+    #[test]
+    fn svg_draws_unowned_ranges() {
+        let mut ring = HashRing::with_datacenters(4, [("dc1", 1)]);
+        ring.batch_add(vec![DcNode::new("b1", "dc2"), DcNode::new("b2", "dc2")]);
+
+        let svg = ring.to_svg();
+
+        assert_eq!(
+            ring.get_hash_ranges().len(),
+            svg.matches(&format!(r#"stroke="{UNOWNED}""#)).count()
+        );
+        assert!(svg.contains("unowned"));
+        assert_eq!(2, svg.matches("<rect").count());
+    }
+
+    #[test]
+    fn dot_groups_transfers_by_source_and_target() {
+        let original = HashRing::from_tokens(
+            0,
+            vec![("a", 1 << 62), ("b", 2 << 62), ("a", 3 << 62), ("b", 0)],
+        )
+        .unwrap();
+
+        // c takes 1..=1<<61 and (4<<61)+1..=5<<61 from a and (6<<61)+1..=7<<61 from b
+        let mut new = original.clone();
+        new.add_with_tokens("c", vec![1 << 61, 5 << 61, 7 << 61])
+            .unwrap();
+
+        let mut migration = Migration::plan(&new, &original, &["a"]);
+        let dot = migration.to_dot();
+
+        // edges are ordered by target, b is not available and has no source for its own hash ranges either
+        let mut lines: Vec<&str> = dot.lines().collect();
+        lines[5..7].sort();
+        assert_eq!(
+            vec![
+                "digraph migration {",
+                "  rankdir=LR;",
+                "  node [shape=box];",
+                "  unavailable [shape=octagon, color=red];",
+                "  unavailable -> \"b\" [label=\"3 ranges\\n37.50%\"];",
+                "  \"a\" -> \"c\" [label=\"2 ranges\\n25.00%\"];",
+                "  unavailable -> \"c\" [label=\"1 ranges\\n12.50%\"];",
+                "}",
+            ],
+            lines
+        );
+
+        for (target, plan) in migration.remaining_plans() {
+            for replicas in plan {
+                migration.mark_done(&target, &replicas.hash_range);
+            }
+        }
+
+        let dot = migration.to_dot();
+        assert!(dot.contains("  \"a\" -> \"c\" [label=\"2 ranges\\n25.00%\", style=dashed];\n"));
+        assert_eq!(3, dot.matches("style=dashed").count());
+    }
+}
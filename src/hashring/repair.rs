@@ -0,0 +1,146 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use super::HashRing;
+use super::quorum::ConflictResolver;
+use super::strategy::ReplicationStrategy;
+
+/// result of `HashRing::read_repair`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadRepair<T, V> {
+    /// the value that wins against all other responses
+    pub value: V,
+    /// nodes responsible for the key that returned no or an outdated value and need a repair write
+    pub repair: Vec<T>,
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// picks the winning value of all responses for `key` and returns the nodes that need a repair write
+    ///
+    /// Only nodes responsible for `key` (as returned by `get`) are taken into account. Nodes without response
+    /// are not repaired, since their state is unknown. Returns None if no node returned a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key that was read
+    /// * `responses` - value returned by each node, None if the node does not store the key
+    /// * `resolver` - implementation of ConflictResolver to pick the winning value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, LastWriteWins, Versioned};
+    ///
+    /// let mut ring = HashRing::new(2, 10);
+    /// ring.batch_add(vec!["node1", "node2", "node3"]);
+    ///
+    /// let nodes = ring.get(&"foo");
+    /// let responses = vec![
+    ///     (nodes[0], Some(Versioned { version: 2, value: "new" })),
+    ///     (nodes[1], Some(Versioned { version: 1, value: "old" })),
+    ///     (nodes[2], None),
+    /// ];
+    ///
+    /// let repair = ring.read_repair(&"foo", responses, &LastWriteWins).unwrap();
+    ///
+    /// assert_eq!("new", repair.value.value);
+    /// assert_eq!(vec![nodes[1], nodes[2]], repair.repair);
+    /// ```
+    pub fn read_repair<U, V, C>(
+        &self,
+        key: &U,
+        responses: Vec<(T, Option<V>)>,
+        resolver: &C,
+    ) -> Option<ReadRepair<T, V>>
+    where
+        U: Hash,
+        V: Clone + PartialEq,
+        C: ConflictResolver<V>,
+    {
+        let nodes = self.get(key);
+        let responses: Vec<(T, Option<V>)> = responses
+            .into_iter()
+            .filter(|(node, _)| nodes.contains(node))
+            .collect();
+
+        let value = responses
+            .iter()
+            .filter_map(|(_, value)| value.clone())
+            .reduce(|a, b| resolver.resolve(a, b))?;
+
+        let repair = responses
+            .into_iter()
+            .filter(|(_, response)| response.as_ref() != Some(&value))
+            .map(|(node, _)| node)
+            .collect();
+
+        Some(ReadRepair { value, repair })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::fixtures::ring;
+    use crate::hashring::quorum::{LastWriteWins, Versioned};
+    use pretty_assertions::assert_eq;
+
+    fn versioned(version: u64) -> Option<Versioned<u64>> {
+        Some(Versioned {
+            version,
+            value: version * 10,
+        })
+    }
+
+    #[test]
+    fn read_repair_returns_outdated_and_missing_nodes() {
+        let ring = ring(2, &["a", "b", "c", "d", "e"]);
+        let nodes = ring.get(&"foo");
+
+        let repair = ring
+            .read_repair(
+                &"foo",
+                vec![
+                    (nodes[0], versioned(1)),
+                    (nodes[1], None),
+                    (nodes[2], versioned(3)),
+                ],
+                &LastWriteWins,
+            )
+            .unwrap();
+
+        assert_eq!(versioned(3).unwrap(), repair.value);
+        assert_eq!(vec![nodes[0], nodes[1]], repair.repair);
+    }
+
+    #[test]
+    fn read_repair_ignores_unrelated_and_missing_responses() {
+        let ring = ring(2, &["a", "b", "c", "d", "e"]);
+        let nodes = ring.get(&"foo");
+        let other = ring
+            .nodes()
+            .into_iter()
+            .find(|n| !nodes.contains(n))
+            .unwrap();
+
+        let repair = ring
+            .read_repair(
+                &"foo",
+                vec![(nodes[0], versioned(2)), (other, versioned(5))],
+                &LastWriteWins,
+            )
+            .unwrap();
+
+        assert_eq!(versioned(2).unwrap(), repair.value);
+        assert!(repair.repair.is_empty());
+
+        assert_eq!(
+            None,
+            ring.read_repair(&"foo", vec![(nodes[0], None)], &|a: u64, b: u64| a.max(b))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use super::HashRing;
+use super::coordinator::Replicas;
+use super::strategy::ReplicationStrategy;
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// same as `find_sources`, but the source nodes of each hash range are ordered by the given cost function (lowest cost first)
+    ///
+    /// Nodes with equal cost keep the order of `find_sources`.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - return replication sources for this node. target needs to be member of the current HashRing
+    /// * `source` - find replication nodes within this HashRing
+    /// * `available_nodes` - define all nodes that can be used for replication in source HashRing
+    /// * `cost` - cost to replicate from the given source node to `target`, e.g. 0 for the same rack, 1 for the same zone, 2 otherwise
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let nodes = vec!["rack1-a", "rack2-a", "rack1-b"];
+    ///
+    /// let mut ring_original = HashRing::new(1, 10);
+    /// ring_original.batch_add(nodes.clone());
+    ///
+    /// let mut ring_new = ring_original.clone();
+    /// ring_new.add("rack1-c");
+    ///
+    /// let sources = ring_new.find_sources_ranked(&"rack1-c", &ring_original, &nodes, |node| {
+    ///     !node.starts_with("rack1")
+    /// });
+    ///
+    /// for replicas in sources {
+    ///     assert!(replicas.nodes[0].starts_with("rack1"));
+    /// }
+    /// ```
+    pub fn find_sources_ranked<S2, R2, C, F>(
+        &self,
+        target: &T,
+        source: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+        cost: F,
+    ) -> Vec<Replicas<T>>
+    where
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+        C: Ord,
+        F: Fn(&T) -> C,
+    {
+        let mut sources = self.find_sources(target, source, available_nodes);
+
+        for replicas in sources.iter_mut() {
+            replicas.nodes.sort_by_key(|node| cost(node));
+        }
+
+        sources
+    }
+
+    /// same as `find_sources_ranked`, but transfers are spread across all sources with the lowest cost
+    ///
+    /// For each hash range, the cheapest source that was assigned the least amount of hash space so far becomes
+    /// the first node. Large hash ranges are assigned first, thus no single node becomes the sole donor during a join.
+    pub fn find_sources_spread<S2, R2, C, F>(
+        &self,
+        target: &T,
+        source: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+        cost: F,
+    ) -> Vec<Replicas<T>>
+    where
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+        C: Ord,
+        F: Fn(&T) -> C,
+    {
+        let mut sources = self.find_sources_ranked(target, source, available_nodes, &cost);
+
+        let mut order: Vec<usize> = (0..sources.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(sources[*i].width()));
+
+        let mut assigned: Vec<(T, u128)> = vec![];
+
+        for i in order {
+            let nodes = &mut sources[i].nodes;
+            let Some(first) = nodes.first() else {
+                continue;
+            };
+
+            let lowest = cost(first);
+            let cheapest = nodes.iter().take_while(|node| cost(node) == lowest).count();
+
+            let donor = (0..cheapest)
+                .min_by_key(|n| assigned_width(&assigned, &nodes[*n]))
+                .unwrap_or(0);
+
+            let node = nodes.remove(donor);
+            nodes.insert(0, node);
+
+            let width = sources[i].width();
+            let donor = &sources[i].nodes[0];
+            match assigned.iter_mut().find(|(node, _)| node == donor) {
+                Some((_, total)) => *total += width,
+                None => assigned.push((donor.clone(), width)),
+            }
+        }
+
+        sources
+    }
+}
+
+fn assigned_width<T: PartialEq>(assigned: &[(T, u128)], node: &T) -> u128 {
+    assigned
+        .iter()
+        .find(|(n, _)| n == node)
+        .map(|(_, width)| *width)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashring::HashRing;
+    use pretty_assertions::assert_eq;
+
+    fn rings() -> (HashRing<&'static str>, HashRing<&'static str>) {
+        let mut ring_original = HashRing::new(2, 20);
+        ring_original.batch_add(vec!["a", "b", "c", "d", "e"]);
+
+        let mut ring_new = ring_original.clone();
+        ring_new.add("f");
+
+        (ring_original, ring_new)
+    }
+
+    #[test]
+    fn find_sources_ranked_orders_by_cost() {
+        let (ring_original, ring_new) = rings();
+        let available = ["a", "b", "c", "d", "e"];
+
+        let sources = ring_new.find_sources_ranked(&"f", &ring_original, &available, |node| {
+            if *node == "c" { 0 } else { 1 }
+        });
+
+        assert!(!sources.is_empty());
+        for replicas in sources {
+            if replicas.nodes.contains(&"c") {
+                assert_eq!("c", replicas.nodes[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn find_sources_ranked_keeps_all_ranges() {
+        let (ring_original, ring_new) = rings();
+        let available = ["a", "b", "c", "d", "e"];
+
+        let mut plain = ring_new.find_sources(&"f", &ring_original, &available);
+        let mut ranked = ring_new.find_sources_ranked(&"f", &ring_original, &available, |_| 0);
+
+        plain.sort_by_key(|r| *r.hash_range.start());
+        ranked.sort_by_key(|r| *r.hash_range.start());
+
+        assert_eq!(plain, ranked);
+    }
+
+    #[test]
+    fn find_sources_spread_uses_several_donors() {
+        let (ring_original, ring_new) = rings();
+        let available = ["a", "b", "c", "d", "e"];
+
+        let mut ranked = ring_new.find_sources_ranked(&"f", &ring_original, &available, |_| 0);
+        let mut spread = ring_new.find_sources_spread(&"f", &ring_original, &available, |_| 0);
+
+        ranked.sort_by_key(|r| *r.hash_range.start());
+        spread.sort_by_key(|r| *r.hash_range.start());
+
+        let mut donors = vec![];
+        for replicas in spread.iter() {
+            if !donors.contains(&replicas.nodes[0]) {
+                donors.push(replicas.nodes[0]);
+            }
+        }
+        assert!(donors.len() > 1, "transfers should not rely on one donor");
+
+        for (r, s) in ranked.iter().zip(spread.iter()) {
+            assert_eq!(r.hash_range, s.hash_range);
+
+            let mut r_nodes = r.nodes.clone();
+            let mut s_nodes = s.nodes.clone();
+            r_nodes.sort();
+            s_nodes.sort();
+            assert_eq!(r_nodes, s_nodes);
+        }
+    }
+
+    #[test]
+    fn find_sources_spread_respects_cost() {
+        let (ring_original, ring_new) = rings();
+        let available = ["a", "b", "c", "d", "e"];
+
+        let spread = ring_new.find_sources_spread(&"f", &ring_original, &available, |node| {
+            if *node == "a" || *node == "b" { 0 } else { 1 }
+        });
+
+        for replicas in spread {
+            if replicas.nodes.contains(&"a") || replicas.nodes.contains(&"b") {
+                assert!(replicas.nodes[0] == "a" || replicas.nodes[0] == "b");
+            }
+        }
+    }
+}
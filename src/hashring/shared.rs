@@ -0,0 +1,206 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+
+use super::strategy::{ReplicationStrategy, SimpleStrategy};
+use super::{DefaultHashBuilder, HashRing};
+
+/// immutable version of a `HashRing`, published by `SharedRing`
+///
+/// Dereferences to `HashRing`, thus all lookups (`get`, `get_hash_ranges`, ...) can be called directly.
+#[derive(Debug)]
+pub struct Snapshot<T, S = DefaultHashBuilder, R = SimpleStrategy> {
+    epoch: u64,
+    ring: HashRing<T, S, R>,
+}
+
+impl<T, S, R> Snapshot<T, S, R> {
+    /// number of updates published before this snapshot, starting with 0
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// returns the HashRing of this snapshot
+    pub fn ring(&self) -> &HashRing<T, S, R> {
+        &self.ring
+    }
+}
+
+impl<T, S, R> Deref for Snapshot<T, S, R> {
+    type Target = HashRing<T, S, R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ring
+    }
+}
+
+/// SharedRing shares one `HashRing` across many threads without locking readers
+///
+/// Readers `load` the current snapshot, which never changes and can be used as long as needed.
+/// Writers apply changes on a copy of the current HashRing and publish it atomically with a new epoch (RCU).
+/// Writers are serialized, readers never wait for them.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use hashring_coordinator::{HashRing, SharedRing};
+///
+/// let mut ring = HashRing::new(1, 10);
+/// ring.batch_add(vec!["node1", "node2"]);
+///
+/// let shared = Arc::new(SharedRing::new(ring));
+///
+/// let reader = {
+///     let shared = shared.clone();
+///     thread::spawn(move || {
+///         let snapshot = shared.load();
+///         (snapshot.epoch(), snapshot.get(&"foo"))
+///     })
+/// };
+///
+/// let epoch = shared.add("node3");
+/// assert_eq!(1, epoch);
+///
+/// let (seen, nodes) = reader.join().unwrap();
+/// assert!(seen <= epoch);
+/// assert_eq!(2, nodes.len());
+/// ```
+#[derive(Debug)]
+pub struct SharedRing<T, S = DefaultHashBuilder, R = SimpleStrategy> {
+    current: ArcSwap<Snapshot<T, S, R>>,
+    writer: Mutex<()>,
+}
+
+impl<T, S, R> SharedRing<T, S, R> {
+    /// Create a new `SharedRing` publishing `ring` with epoch 0
+    pub fn new(ring: HashRing<T, S, R>) -> Self {
+        SharedRing {
+            current: ArcSwap::from_pointee(Snapshot { epoch: 0, ring }),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// returns the current snapshot (wait-free)
+    pub fn load(&self) -> Arc<Snapshot<T, S, R>> {
+        self.current.load_full()
+    }
+
+    /// returns the epoch of the current snapshot
+    pub fn epoch(&self) -> u64 {
+        self.current.load().epoch
+    }
+}
+
+impl<T, S, R> SharedRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher + Clone,
+    R: ReplicationStrategy<T> + Clone,
+{
+    /// apply `update` on a copy of the current HashRing and publish it, returns the new epoch
+    ///
+    /// Concurrent updates are applied one after another, no update gets lost.
+    pub fn update<F>(&self, update: F) -> u64
+    where
+        F: FnOnce(&mut HashRing<T, S, R>),
+    {
+        let _writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+
+        let current = self.current.load();
+        let mut ring = current.ring.clone();
+        update(&mut ring);
+
+        let epoch = current.epoch + 1;
+        self.current.store(Arc::new(Snapshot { epoch, ring }));
+
+        epoch
+    }
+
+    /// add `node` and publish the new HashRing, returns the new epoch
+    pub fn add(&self, node: T) -> u64 {
+        self.update(|ring| ring.add(node))
+    }
+
+    /// remove `node` and publish the new HashRing, returns the new epoch
+    pub fn remove(&self, node: &T) -> u64 {
+        self.update(|ring| ring.remove(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::thread;
+
+    #[test]
+    fn snapshots_do_not_change_after_updates() {
+        let mut ring = HashRing::new(1, 10);
+        ring.batch_add(vec!["a", "b"]);
+
+        let shared = SharedRing::new(ring);
+        let before = shared.load();
+
+        assert_eq!(1, shared.add("c"));
+        assert_eq!(2, shared.remove(&"a"));
+
+        assert_eq!(0, before.epoch());
+        assert_eq!(2, before.len());
+
+        let after = shared.load();
+        assert_eq!(2, after.epoch());
+        assert_eq!(vec!["b", "c"], {
+            let mut nodes = after.nodes();
+            nodes.sort();
+            nodes
+        });
+    }
+
+    #[test]
+    fn readers_see_increasing_epochs_while_writers_update() {
+        let mut ring = HashRing::new(1, 10);
+        ring.add(0);
+
+        let shared = Arc::new(SharedRing::new(ring));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for key in 0..1000 {
+                        let snapshot = shared.load();
+                        assert!(snapshot.epoch() >= last);
+                        assert_eq!(snapshot.epoch() as usize + 1, snapshot.len());
+                        assert!(!snapshot.get(&key).is_empty());
+                        last = snapshot.epoch();
+                    }
+                })
+            })
+            .collect();
+
+        let writers: Vec<_> = (0..2)
+            .map(|w| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for node in 0..50 {
+                        shared.add(1 + w * 50 + node);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in readers.into_iter().chain(writers) {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(100, shared.epoch());
+        assert_eq!(101, shared.load().len());
+    }
+}
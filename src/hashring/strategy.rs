@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+
+/// ReplicationStrategy selects the primary node and all replica nodes for a position on the ring.
+///
+/// The strategy is consulted by `HashRing::get`, `HashRing::get_hash_ranges` and `HashRing::find_sources`.
+/// To keep hash ranges consistent, the selection must only depend on the given arguments (all keys within a hash range
+/// share the same `token` and `clockwise` order).
+pub trait ReplicationStrategy<T> {
+    /// returns all nodes that store keys at the given position, the first node is the primary node
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - position of the virtual node that is responsible for the key
+    /// * `clockwise` - real nodes of all virtual nodes, walking the ring clockwise starting at `token` (real nodes repeat)
+    /// * `replicas` - number of replicas configured for the HashRing
+    fn select<'a, I>(&self, token: u64, clockwise: I, replicas: usize) -> Vec<T>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a;
+
+    /// reorders the source nodes that can replicate a hash range to `target`, preferred sources first
+    ///
+    /// used by `HashRing::find_sources`, the default keeps the ring order of the source HashRing
+    fn rank_sources(&self, _target: &T, _sources: &mut [T]) {}
+}
+
+/// SimpleStrategy uses the next `replicas + 1` distinct nodes clockwise (default)
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SimpleStrategy;
+
+impl<T> ReplicationStrategy<T> for SimpleStrategy
+where
+    T: Clone + PartialEq,
+{
+    fn select<'a, I>(&self, _token: u64, clockwise: I, replicas: usize) -> Vec<T>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut nodes = vec![];
+
+        for node in clockwise {
+            if !nodes.contains(node) {
+                nodes.push(node.clone());
+
+                if nodes.len() == replicas + 1 {
+                    break;
+                }
+            }
+        }
+
+        nodes
+    }
+}
+
+/// Nodes declare the datacenter they belong to, to be used with `NetworkTopologyStrategy`
+pub trait Datacenter {
+    fn datacenter(&self) -> &str;
+}
+
+/// NetworkTopologyStrategy stores a fixed number of copies per datacenter (ignoring the replicas of the HashRing)
+///
+/// Walking the ring clockwise, each node is selected until its datacenter holds the configured number of copies.
+/// Datacenters that are not configured do not store any copies.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct NetworkTopologyStrategy {
+    copies: BTreeMap<String, usize>,
+}
+
+impl NetworkTopologyStrategy {
+    /// # Arguments
+    ///
+    /// * `copies` - number of copies (primary + replicas) per datacenter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::NetworkTopologyStrategy;
+    ///
+    /// let strategy = NetworkTopologyStrategy::new([("dc1", 3), ("dc2", 2)]);
+    ///
+    /// assert_eq!(Some(3), strategy.copies("dc1"));
+    /// ```
+    pub fn new<D: Into<String>>(copies: impl IntoIterator<Item = (D, usize)>) -> Self {
+        NetworkTopologyStrategy {
+            copies: copies.into_iter().map(|(dc, n)| (dc.into(), n)).collect(),
+        }
+    }
+
+    /// returns the number of copies stored in the given datacenter
+    pub fn copies(&self, datacenter: &str) -> Option<usize> {
+        self.copies.get(datacenter).copied()
+    }
+
+    /// returns all configured datacenters
+    pub fn datacenters(&self) -> impl Iterator<Item = &str> {
+        self.copies.keys().map(|dc| dc.as_str())
+    }
+}
+
+impl<T> ReplicationStrategy<T> for NetworkTopologyStrategy
+where
+    T: Clone + PartialEq + Datacenter,
+{
+    fn select<'a, I>(&self, _token: u64, clockwise: I, _replicas: usize) -> Vec<T>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut missing = self.copies.clone();
+        missing.retain(|_, n| *n > 0);
+
+        let mut nodes = vec![];
+
+        for node in clockwise {
+            if missing.is_empty() {
+                break;
+            }
+
+            if nodes.contains(node) {
+                continue;
+            }
+
+            if let Some(n) = missing.get_mut(node.datacenter()) {
+                nodes.push(node.clone());
+
+                *n -= 1;
+                if *n == 0 {
+                    missing.remove(node.datacenter());
+                }
+            }
+        }
+
+        nodes
+    }
+
+    /// sources within the datacenter of `target` are preferred
+    fn rank_sources(&self, target: &T, sources: &mut [T]) {
+        sources.sort_by_key(|node| node.datacenter() != target.datacenter());
+    }
+}
+
+/// RandomStrategy uses the next node clockwise as primary and `replicas` random distinct nodes as replicas
+///
+/// Replicas are the nodes of randomly chosen virtual nodes, thus nodes with more virtual nodes are chosen more often.
+/// The random choice is seeded by the token of the primary virtual node, thus each hash range always
+/// maps to the same nodes. Use a different `seed` to get a different (but again stable) placement.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RandomStrategy {
+    seed: u64,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        RandomStrategy { seed }
+    }
+}
+
+impl<T> ReplicationStrategy<T> for RandomStrategy
+where
+    T: Clone + PartialEq,
+{
+    fn select<'a, I>(&self, token: u64, clockwise: I, replicas: usize) -> Vec<T>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a,
+    {
+        let vnodes: Vec<&T> = clockwise.collect();
+        let Some(primary) = vnodes.first() else {
+            return vec![];
+        };
+
+        let mut selected: Vec<&T> = vec![primary];
+        let mut state = self.seed ^ token;
+
+        // pick random virtual nodes until enough distinct nodes are selected
+        // the number of attempts is limited, as the ring may have fewer distinct nodes than requested
+        for _ in 0..(replicas + 1) * 8 {
+            if selected.len() > replicas || vnodes.len() < 2 {
+                break;
+            }
+
+            let i = 1 + (splitmix64(&mut state) % (vnodes.len() - 1) as u64) as usize;
+            if !selected.contains(&vnodes[i]) {
+                selected.push(vnodes[i]);
+            }
+        }
+
+        // fill up with the next distinct nodes clockwise
+        for node in vnodes.iter() {
+            if selected.len() > replicas {
+                break;
+            }
+            if !selected.contains(node) {
+                selected.push(node);
+            }
+        }
+
+        selected.into_iter().cloned().collect()
+    }
+}
+
+// small, seedable pseudo random number generator (http://xorshift.di.unimi.it/splitmix64.c)
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashring::HashRing;
+    use crate::hashring::coordinator::Replicas;
+    use crate::hashring::fixtures::DcNode as Node;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn simple_strategy_selects_next_distinct_nodes() {
+        let clockwise = ["a", "b", "a", "c", "d"];
+
+        let nodes = SimpleStrategy.select(0, clockwise.iter(), 2);
+
+        assert_eq!(vec!["a", "b", "c"], nodes);
+    }
+
+    #[test]
+    fn simple_strategy_is_limited_by_ring_size() {
+        let clockwise = ["a", "b", "a", "b"];
+
+        let nodes = SimpleStrategy.select(0, clockwise.iter(), 5);
+
+        assert_eq!(vec!["a", "b"], nodes);
+    }
+
+    #[test]
+    fn network_topology_strategy_selects_copies_per_datacenter() {
+        let a1 = Node::new("a1", "dc1");
+        let a2 = Node::new("a2", "dc1");
+        let a3 = Node::new("a3", "dc1");
+        let a4 = Node::new("a4", "dc1");
+        let b1 = Node::new("b1", "dc2");
+        let b2 = Node::new("b2", "dc2");
+        let c1 = Node::new("c1", "dc3");
+
+        let clockwise = [&a1, &a2, &c1, &a1, &b1, &a3, &a4, &b2];
+        let strategy = NetworkTopologyStrategy::new([("dc1", 3), ("dc2", 1)]);
+
+        let nodes = strategy.select(0, clockwise.into_iter(), 0);
+
+        assert_eq!(vec![a1, a2, b1, a3], nodes);
+    }
+
+    #[test]
+    fn random_strategy_keeps_primary_and_is_stable() {
+        let clockwise = ["a", "b", "c", "d", "e", "f"];
+        let strategy = RandomStrategy::new(7);
+
+        let nodes = strategy.select(123, clockwise.iter(), 2);
+
+        assert_eq!(3, nodes.len());
+        assert_eq!("a", nodes[0]);
+        assert!(!nodes[1..].contains(&"a"));
+        assert_ne!(nodes[1], nodes[2]);
+        assert_eq!(nodes, strategy.select(123, clockwise.iter(), 2));
+    }
+
+    #[test]
+    fn random_strategy_selects_replicas_beyond_the_next_nodes() {
+        let clockwise = ["a", "b", "c", "d", "e", "f", "a", "b", "c", "d", "e", "f"];
+        let strategy = RandomStrategy::new(7);
+
+        let mut replicas = vec![];
+        for token in 0..100 {
+            let nodes = strategy.select(token, clockwise.iter(), 1);
+
+            assert_eq!(vec!["a", nodes[1]], nodes);
+            if !replicas.contains(&nodes[1]) {
+                replicas.push(nodes[1]);
+            }
+        }
+        assert_eq!(5, replicas.len());
+
+        // fewer distinct nodes than requested
+        assert_eq!(
+            vec!["a", "b"],
+            strategy.select(0, ["a", "b", "a"].iter(), 5)
+        );
+    }
+
+    #[test]
+    fn single_node_ring_consults_strategy_for_hash_ranges() {
+        let strategy = NetworkTopologyStrategy::new([("dc1", 1)]);
+        let mut ring = HashRing::with_strategy(0, 10, strategy);
+        ring.add(Node::new("b1", "dc2"));
+
+        assert!(ring.get(&"foo").is_empty());
+        assert_eq!(
+            vec![Replicas {
+                hash_range: 0..=u64::MAX,
+                nodes: vec![],
+            }],
+            ring.get_hash_ranges()
+        );
+    }
+
+    #[test]
+    fn ring_consults_strategy_for_get_and_hash_ranges() {
+        let nodes = vec![
+            Node::new("a1", "dc1"),
+            Node::new("a2", "dc1"),
+            Node::new("a3", "dc1"),
+            Node::new("b1", "dc2"),
+            Node::new("b2", "dc2"),
+        ];
+
+        let strategy = NetworkTopologyStrategy::new([("dc1", 2), ("dc2", 1)]);
+        let mut ring = HashRing::with_strategy(0, 10, strategy);
+        ring.batch_add(nodes.clone());
+
+        for replicas in ring.get_hash_ranges() {
+            assert_eq!(3, replicas.nodes.len());
+            assert_eq!(2, replicas.nodes.iter().filter(|n| n.dc == "dc1").count());
+            assert_eq!(replicas.nodes, ring.get_by_hash(*replicas.hash_range.end()));
+        }
+
+        let mut ring_new = ring.clone();
+        let joining = Node::new("b3", "dc2");
+        ring_new.add(joining.clone());
+
+        for replicas in ring_new.find_sources(&joining, &ring, &nodes) {
+            assert!(!replicas.nodes.is_empty());
+        }
+    }
+
+    #[test]
+    fn random_strategy_keeps_keys_within_hash_range_together() {
+        let mut ring = HashRing::with_strategy(1, 5, RandomStrategy::new(1));
+        ring.batch_add(vec!["node1", "node2", "node3", "node4", "node5"]);
+
+        for replicas in ring.get_hash_ranges() {
+            let start = *replicas.hash_range.start();
+            let end = *replicas.hash_range.end();
+
+            assert_eq!(replicas.nodes, ring.get_by_hash(start));
+            assert_eq!(replicas.nodes, ring.get_by_hash(end));
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use super::{HashRing, Node};
+
+/// error of an explicit token assignment
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenError {
+    /// the token is already assigned to a virtual node, tokens need to be unique across the ring
+    DuplicateToken(u64),
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::DuplicateToken(token) => {
+                write!(f, "token {token} is assigned more than once")
+            }
+        }
+    }
+}
+
+impl Error for TokenError {}
+
+impl<T> HashRing<T>
+where
+    T: Clone + PartialEq,
+{
+    /// Create a new `HashRing` from explicitly assigned tokens (as known from Cassandra) instead of hashing `(node, id)`.
+    /// Each token is the position of one virtual node on the ring, a node can own any number of tokens.
+    /// Tokens need to be unique across the ring, returns `TokenError::DuplicateToken` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - number of nodes to store copies of each key (set replicas to 0, to store each key only once)
+    /// * `tokens` - list of `(node, token)` pairs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::HashRing;
+    ///
+    /// let ring = HashRing::from_tokens(0, vec![("node1", 100), ("node2", 200), ("node1", 300)]).unwrap();
+    ///
+    /// assert_eq!(2, ring.len());
+    /// assert_eq!(vec!["node2"], ring.get_by_hash(150));
+    /// assert_eq!(vec!["node1"], ring.get_by_hash(250));
+    /// ```
+    pub fn from_tokens(replicas: usize, tokens: Vec<(T, u64)>) -> Result<HashRing<T>, TokenError> {
+        let mut ring = HashRing::new(replicas, 1);
+
+        // number of tokens per node so far, counted once instead of scanning the ring for each token
+        let mut counts: Vec<(T, usize)> = vec![];
+
+        for (node, token) in tokens {
+            let virtual_id = match counts.iter_mut().find(|(n, _)| *n == node) {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count - 1
+                }
+                None => {
+                    counts.push((node.clone(), 1));
+                    0
+                }
+            };
+
+            ring.push_token(node, token, virtual_id);
+        }
+        ring.ring.sort();
+
+        if let Some(pair) = ring.ring.windows(2).find(|pair| pair[0].key == pair[1].key) {
+            return Err(TokenError::DuplicateToken(pair[0].key));
+        }
+
+        Ok(ring)
+    }
+}
+
+impl<T, S, R> HashRing<T, S, R>
+where
+    T: Clone + PartialEq,
+{
+    /// Add `node` to the hash ring using the given tokens instead of hashing `(node, id)`.
+    /// Tokens need to be unique across the ring, returns `TokenError::DuplicateToken` (without adding any token) otherwise.
+    pub fn add_with_tokens(&mut self, node: T, tokens: Vec<u64>) -> Result<(), TokenError> {
+        let mut sorted = tokens.clone();
+        sorted.sort_unstable();
+
+        for (i, token) in sorted.iter().enumerate() {
+            let assigned = self.ring.binary_search_by(|n| n.key.cmp(token)).is_ok();
+            if assigned || sorted.get(i + 1) == Some(token) {
+                return Err(TokenError::DuplicateToken(*token));
+            }
+        }
+
+        let existing = self.ring.iter().filter(|n| n.node == node).count();
+        for (i, token) in tokens.into_iter().enumerate() {
+            self.push_token(node.clone(), token, existing + i);
+        }
+        self.ring.sort();
+
+        Ok(())
+    }
+
+    /// Add `node` to the hash ring with `vnodes` tokens chosen by `allocate_tokens`.
+    ///
+    /// Returns the allocated tokens
+    pub fn add_with_allocated_tokens(&mut self, node: T) -> Vec<u64> {
+        let tokens = self.allocate_tokens(self.vnodes);
+        self.add_with_tokens(node, tokens.clone())
+            .expect("allocated tokens are unique");
+
+        tokens
+    }
+
+    /// choose `count` new tokens, each one splits the currently largest range of the ring in half
+    ///
+    /// Tokens chosen earlier in the same call are taken into account, thus a node joining with several tokens
+    /// takes over a share of the largest ranges instead of splitting the same range several times.
+    /// Returns fewer tokens if no range can be split anymore.
+    pub fn allocate_tokens(&self, count: usize) -> Vec<u64> {
+        let mut tokens: Vec<u64> = self.ring.iter().map(|n| n.key).collect();
+        let mut allocated = vec![];
+
+        for _ in 0..count {
+            let token = match largest_range(&tokens) {
+                None => u64::MAX,
+                Some((_, width)) if width < 2 => break,
+                Some((left, width)) => (left as u128 + width / 2) as u64,
+            };
+
+            if let Err(pos) = tokens.binary_search(&token) {
+                tokens.insert(pos, token);
+            }
+            allocated.push(token);
+        }
+
+        allocated
+    }
+
+    /// returns all `(node, token)` pairs of the ring in ring order
+    pub fn tokens(&self) -> Vec<(T, u64)> {
+        self.ring.iter().map(|n| (n.node.clone(), n.key)).collect()
+    }
+
+    // adds a single token without sorting the ring, virtual ids are numbered per node
+    fn push_token(&mut self, node: T, token: u64, virtual_id: usize) {
+        self.vnodes = self.vnodes.max(virtual_id + 1);
+        if virtual_id == 0 {
+            self.len += 1;
+        }
+        self.ring.push(Node::new(token, node, virtual_id));
+    }
+}
+
+// returns the exclusive left token and the width of the largest range between two neighboring tokens
+// the range left of the first token wraps around, its width is 2^64 if there is only one token
+fn largest_range(tokens: &[u64]) -> Option<(u64, u128)> {
+    let first = *tokens.first()?;
+    let last = *tokens.last()?;
+
+    let mut largest = (last, first as u128 + (1 << 64) - last as u128);
+
+    for pair in tokens.windows(2) {
+        let width = (pair[1] - pair[0]) as u128;
+        if width > largest.1 {
+            largest = (pair[0], width);
+        }
+    }
+
+    Some(largest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenError;
+    use crate::hashring::coordinator::Replicas;
+    use crate::hashring::{DefaultHashBuilder, HashRing};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_tokens_uses_given_positions() {
+        let mut ring = HashRing::from_tokens(
+            1,
+            vec![
+                ("node1", 1000),
+                ("node2", 2000),
+                ("node3", 3000),
+                ("node1", 4000),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(3, ring.len());
+        assert_eq!(4, ring.vlen());
+        assert_eq!(vec!["node1", "node2", "node3"], ring.nodes());
+        assert_eq!(vec!["node1", "node2"], ring.get_by_hash(0));
+        assert_eq!(vec!["node2", "node3"], ring.get_by_hash(2000));
+        assert_eq!(vec!["node1", "node2"], ring.get_by_hash(3001));
+        assert_eq!(vec!["node1", "node2"], ring.get_by_hash(4001));
+
+        let expected = vec![
+            Replicas {
+                hash_range: 4001..=u64::MAX,
+                nodes: vec!["node1", "node2"],
+            },
+            Replicas {
+                hash_range: 0..=1000,
+                nodes: vec!["node1", "node2"],
+            },
+            Replicas {
+                hash_range: 1001..=2000,
+                nodes: vec!["node2", "node3"],
+            },
+            Replicas {
+                hash_range: 2001..=3000,
+                nodes: vec!["node3", "node1"],
+            },
+            Replicas {
+                hash_range: 3001..=4000,
+                nodes: vec!["node1", "node2"],
+            },
+        ];
+
+        assert_eq!(expected, ring.get_hash_ranges());
+
+        ring.remove(&"node1");
+        assert_eq!(2, ring.len());
+        assert_eq!(2, ring.vlen());
+    }
+
+    #[test]
+    fn allocate_tokens_on_empty_ring_spreads_tokens_evenly() {
+        let ring: HashRing<&str> = HashRing::new(0, 4);
+
+        assert_eq!(
+            vec![u64::MAX, (1 << 63) - 1, (1 << 62) - 1, (3 << 62) - 1],
+            ring.allocate_tokens(4)
+        );
+    }
+
+    #[test]
+    fn allocate_tokens_splits_largest_ranges() {
+        let mut ring = HashRing::from_tokens(0, vec![("node1", 0), ("node2", 1000)]).unwrap();
+
+        assert_eq!(vec![(1u128 << 63) as u64 + 500], ring.allocate_tokens(1));
+
+        ring.add_with_tokens("node3", vec![100]).unwrap();
+        assert_eq!(
+            vec![("node1", 0), ("node3", 100), ("node2", 1000)],
+            ring.tokens()
+        );
+
+        let tokens = ring.add_with_allocated_tokens("node4");
+        assert_eq!(vec![(1u128 << 63) as u64 + 500], tokens);
+        assert_eq!(4, ring.len());
+    }
+
+    #[test]
+    fn find_sources_on_token_ring() {
+        let ring_original =
+            HashRing::from_tokens(0, vec![("node1", 1000), ("node2", 2000)]).unwrap();
+
+        let mut ring_new = ring_original.clone();
+        ring_new.add_with_tokens("node3", vec![1500]).unwrap();
+
+        let sources = ring_new.find_sources(&"node3", &ring_original, &["node1", "node2"]);
+        let expected = vec![Replicas {
+            hash_range: 1001..=1500,
+            nodes: vec!["node2"],
+        }];
+
+        assert_eq!(expected, sources);
+    }
+
+    #[test]
+    fn duplicate_tokens_are_rejected() {
+        let duplicate = HashRing::from_tokens(0, vec![("node1", 1000), ("node2", 1000)]);
+        assert_eq!(Err(TokenError::DuplicateToken(1000)), duplicate);
+
+        let mut ring = HashRing::from_tokens(0, vec![("node1", 1000), ("node2", 2000)]).unwrap();
+        let before = ring.clone();
+
+        assert_eq!(
+            Err(TokenError::DuplicateToken(2000)),
+            ring.add_with_tokens("node3", vec![1500, 2000])
+        );
+        assert_eq!(
+            Err(TokenError::DuplicateToken(1500)),
+            ring.add_with_tokens("node3", vec![1500, 1500])
+        );
+        assert_eq!(before, ring);
+    }
+
+    #[test]
+    fn ring_without_vnodes_uses_one_vnode_per_node() {
+        let mut ring: HashRing<&str> = HashRing::with_hasher(0, 0, DefaultHashBuilder);
+        ring.batch_add(vec!["node1", "node2"]);
+
+        assert_eq!(2, ring.len());
+        assert_eq!(2, ring.vlen());
+    }
+}
@@ -133,7 +133,48 @@
 //! }
 //! ```
 
+pub mod gossip;
 mod hashring;
+mod replication;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub use hashring::HashRing;
+pub use hashring::analysis::UnderReplication;
 pub use hashring::coordinator::Replicas;
+pub use hashring::decommission::DecommissionTransfer;
+pub use hashring::detector::{
+    Clock, FailureDetector, ManualClock, PhiAccrualConfig, PhiAccrualDetector, SystemClock,
+};
+pub use hashring::format::{RangeFormat, format_table};
+pub use hashring::handoff::{Handoff, Hint};
+#[cfg(feature = "fnv")]
+pub use hashring::hasher::FnvHashBuilder;
+#[cfg(feature = "xxhash")]
+pub use hashring::hasher::Xxh3HashBuilder;
+#[cfg(feature = "murmur3")]
+pub use hashring::hasher::{Murmur3HashBuilder, Murmur3Hasher};
+pub use hashring::impact::{Impact, NodeImpact};
+pub use hashring::merkle::{MerkleTree, merkle_digest};
+pub use hashring::quorum::{ConflictResolver, LastWriteWins, Quorum, QuorumError, Versioned};
+pub use hashring::repair::ReadRepair;
+#[cfg(feature = "shared")]
+pub use hashring::shared::{SharedRing, Snapshot};
+pub use hashring::strategy::{
+    Datacenter, NetworkTopologyStrategy, RandomStrategy, ReplicationStrategy, SimpleStrategy,
+};
+pub use hashring::token::TokenError;
+pub use replication::executor::{
+    Cancelled, Failed, Progress, RangeSink, RangeSource, ReplicationExecutor, ReplicationReport,
+    TransferError, Transferred,
+};
+pub use replication::membership::{Membership, MembershipEvent};
+pub use replication::migration::{Migration, MigrationTransfer};
+#[cfg(feature = "async")]
+pub use replication::nonblocking::{
+    AsyncRangeSink, AsyncRangeSource, AsyncReplicationExecutor, Limits, MemoryTransport,
+    MemoryTransportError,
+};
+pub use replication::transition::TransitionRing;
@@ -0,0 +1,8 @@
+//! apply replication plans (as returned by `HashRing::find_sources`) to the nodes of a cluster
+
+pub mod executor;
+pub mod membership;
+pub mod migration;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod transition;
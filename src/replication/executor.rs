@@ -0,0 +1,476 @@
+use std::fmt::{self, Debug, Display};
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeInclusive;
+
+use crate::hashring::HashRing;
+use crate::hashring::coordinator::Replicas;
+use crate::hashring::strategy::ReplicationStrategy;
+
+/// RangeSource reads all keys (items) within a hash range from a node
+pub trait RangeSource<T> {
+    type Item;
+    type Error;
+
+    /// returns all items stored on `node` whose key hash is within `hash_range`
+    fn fetch(
+        &mut self,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> Result<Vec<Self::Item>, Self::Error>;
+}
+
+/// RangeSink stores items fetched by a `RangeSource` on a node
+pub trait RangeSink<T, Item> {
+    type Error;
+
+    /// store all `items` on `node`
+    fn store(&mut self, node: &T, items: Vec<Item>) -> Result<(), Self::Error>;
+}
+
+/// error of a single transfer attempt
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransferError<SE, KE> {
+    /// the source node failed to deliver the hash range
+    Source(SE),
+    /// the target node failed to store the items
+    Sink(KE),
+}
+
+impl<SE: Display, KE: Display> Display for TransferError<SE, KE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::Source(e) => write!(f, "failed to fetch hash range: {e}"),
+            TransferError::Sink(e) => write!(f, "failed to store hash range: {e}"),
+        }
+    }
+}
+
+impl<SE: Debug + Display, KE: Debug + Display> std::error::Error for TransferError<SE, KE> {}
+
+/// a hash range that was copied to `target`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transferred<T> {
+    pub target: T,
+    pub hash_range: RangeInclusive<u64>,
+    /// node that delivered the hash range
+    pub source: T,
+    /// number of items copied
+    pub items: usize,
+}
+
+/// a hash range that could not be copied to `target` from any of its source nodes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failed<T, E> {
+    pub target: T,
+    pub hash_range: RangeInclusive<u64>,
+    /// all failed attempts in order, including retries of the same node
+    pub errors: Vec<(T, E)>,
+}
+
+/// a hash range that was not copied to `target`, because the replication was cancelled
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cancelled<T> {
+    pub target: T,
+    pub hash_range: RangeInclusive<u64>,
+}
+
+/// result of applying one or several replication plans
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicationReport<T, E> {
+    pub transferred: Vec<Transferred<T>>,
+    pub failed: Vec<Failed<T, E>>,
+    pub cancelled: Vec<Cancelled<T>>,
+}
+
+impl<T, E> ReplicationReport<T, E> {
+    /// returns true if all hash ranges were copied
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.cancelled.is_empty()
+    }
+
+    /// returns the total number of copied items
+    pub fn items(&self) -> usize {
+        self.transferred.iter().map(|t| t.items).sum()
+    }
+
+    pub(crate) fn extend(&mut self, other: ReplicationReport<T, E>) {
+        self.transferred.extend(other.transferred);
+        self.failed.extend(other.failed);
+        self.cancelled.extend(other.cancelled);
+    }
+}
+
+impl<T, E> Default for ReplicationReport<T, E> {
+    fn default() -> Self {
+        ReplicationReport {
+            transferred: vec![],
+            failed: vec![],
+            cancelled: vec![],
+        }
+    }
+}
+
+/// Progress is reported after each hash range of a plan is finished (copied or failed)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress<'a, T> {
+    pub target: &'a T,
+    pub hash_range: &'a RangeInclusive<u64>,
+    /// node that delivered the hash range, None if all sources failed
+    pub source: Option<&'a T>,
+    /// number of items copied for this hash range
+    pub items: usize,
+    /// number of finished hash ranges of the current plan
+    pub finished: usize,
+    /// number of hash ranges of the current plan
+    pub total: usize,
+}
+
+/// ReplicationExecutor copies hash ranges from source nodes to target nodes
+///
+/// For each hash range the source nodes are tried in the given order (see `find_sources_ranked`),
+/// each node is retried `retries` times before the next node is used as fallback.
+pub struct ReplicationExecutor<Src, Snk> {
+    source: Src,
+    sink: Snk,
+    retries: usize,
+}
+
+impl<Src, Snk> ReplicationExecutor<Src, Snk> {
+    /// Create a new `ReplicationExecutor` that tries each source node once
+    pub fn new(source: Src, sink: Snk) -> Self {
+        ReplicationExecutor::with_retries(source, sink, 0)
+    }
+
+    /// Create a new `ReplicationExecutor`
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - fetches hash ranges from source nodes
+    /// * `sink` - stores fetched items on target nodes
+    /// * `retries` - number of retries per source node, before falling back to the next source node
+    pub fn with_retries(source: Src, sink: Snk, retries: usize) -> Self {
+        ReplicationExecutor {
+            source,
+            sink,
+            retries,
+        }
+    }
+
+    /// returns source and sink
+    pub fn into_inner(self) -> (Src, Snk) {
+        (self.source, self.sink)
+    }
+
+    /// copy all hash ranges of `plan` to `target`
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - node that shall receive all hash ranges
+    /// * `plan` - hash ranges and their source nodes, as returned by `find_sources`
+    /// * `progress` - called after each hash range is finished
+    pub fn execute<T, F>(
+        &mut self,
+        target: &T,
+        plan: &[Replicas<T>],
+        mut progress: F,
+    ) -> ReplicationReport<T, TransferError<Src::Error, Snk::Error>>
+    where
+        T: Clone,
+        Src: RangeSource<T>,
+        Snk: RangeSink<T, Src::Item>,
+        F: FnMut(&Progress<T>),
+    {
+        let mut report = ReplicationReport::default();
+
+        for (n, Replicas { hash_range, nodes }) in plan.iter().enumerate() {
+            let mut errors = vec![];
+            let mut transferred = None;
+
+            'sources: for node in nodes {
+                for _ in 0..=self.retries {
+                    match self.transfer(target, node, hash_range) {
+                        Ok(items) => {
+                            transferred = Some((node, items));
+                            break 'sources;
+                        }
+                        Err(e) => errors.push((node.clone(), e)),
+                    }
+                }
+            }
+
+            progress(&Progress {
+                target,
+                hash_range,
+                source: transferred.map(|(node, _)| node),
+                items: transferred.map(|(_, items)| items).unwrap_or(0),
+                finished: n + 1,
+                total: plan.len(),
+            });
+
+            match transferred {
+                Some((source, items)) => report.transferred.push(Transferred {
+                    target: target.clone(),
+                    hash_range: hash_range.clone(),
+                    source: source.clone(),
+                    items,
+                }),
+                None => report.failed.push(Failed {
+                    target: target.clone(),
+                    hash_range: hash_range.clone(),
+                    errors,
+                }),
+            }
+        }
+
+        report
+    }
+
+    /// copy all keys that are missing on the nodes of `ring` after a change of the cluster (or a new deployment)
+    ///
+    /// calls `find_sources` for each node of `ring` and executes the plan
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - current HashRing, all nodes of this ring are targets
+    /// * `previous` - HashRing to find replication nodes in
+    /// * `available_nodes` - define all nodes that can be used for replication in `previous`
+    /// * `progress` - called after each hash range is finished
+    pub fn replicate<T, S, R, S2, R2, F>(
+        &mut self,
+        ring: &HashRing<T, S, R>,
+        previous: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+        mut progress: F,
+    ) -> ReplicationReport<T, TransferError<Src::Error, Snk::Error>>
+    where
+        T: Hash + Clone + Debug + PartialEq,
+        S: BuildHasher,
+        R: ReplicationStrategy<T>,
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+        Src: RangeSource<T>,
+        Snk: RangeSink<T, Src::Item>,
+        F: FnMut(&Progress<T>),
+    {
+        let mut report = ReplicationReport::default();
+
+        for target in ring.nodes() {
+            let plan = ring.find_sources(&target, previous, available_nodes);
+            report.extend(self.execute(&target, &plan, &mut progress));
+        }
+
+        report
+    }
+
+    fn transfer<T>(
+        &mut self,
+        target: &T,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> Result<usize, TransferError<Src::Error, Snk::Error>>
+    where
+        Src: RangeSource<T>,
+        Snk: RangeSink<T, Src::Item>,
+    {
+        let items = self
+            .source
+            .fetch(node, hash_range)
+            .map_err(TransferError::Source)?;
+        let count = items.len();
+
+        self.sink
+            .store(target, items)
+            .map_err(TransferError::Sink)?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    // each node stores (hash, key) pairs, nodes listed in `down` fail `failures` times before they answer
+    struct Cluster {
+        store: HashMap<&'static str, Vec<(u64, String)>>,
+        down: HashMap<&'static str, usize>,
+    }
+
+    impl RangeSource<&'static str> for Cluster {
+        type Item = (u64, String);
+        type Error = String;
+
+        fn fetch(
+            &mut self,
+            node: &&'static str,
+            hash_range: &RangeInclusive<u64>,
+        ) -> Result<Vec<Self::Item>, Self::Error> {
+            if let Some(failures) = self.down.get_mut(node)
+                && *failures > 0
+            {
+                *failures -= 1;
+                return Err(format!("{node} is down"));
+            }
+
+            Ok(self
+                .store
+                .get(node)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter(|(hash, _)| hash_range.contains(hash))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default())
+        }
+    }
+
+    #[derive(Default)]
+    struct Sink {
+        store: HashMap<&'static str, Vec<(u64, String)>>,
+    }
+
+    impl RangeSink<&'static str, (u64, String)> for Sink {
+        type Error = String;
+
+        fn store(
+            &mut self,
+            node: &&'static str,
+            items: Vec<(u64, String)>,
+        ) -> Result<(), Self::Error> {
+            self.store.entry(node).or_default().extend(items);
+            Ok(())
+        }
+    }
+
+    fn cluster(down: &[(&'static str, usize)]) -> Cluster {
+        let mut store = HashMap::new();
+        store.insert("a", vec![(5, "k5".to_string()), (15, "k15".to_string())]);
+        store.insert("b", vec![(5, "k5".to_string()), (25, "k25".to_string())]);
+
+        Cluster {
+            store,
+            down: down.iter().cloned().collect(),
+        }
+    }
+
+    fn plan() -> Vec<Replicas<&'static str>> {
+        vec![
+            Replicas {
+                hash_range: 0..=10,
+                nodes: vec!["a", "b"],
+            },
+            Replicas {
+                hash_range: 11..=30,
+                nodes: vec!["b"],
+            },
+        ]
+    }
+
+    #[test]
+    fn execute_copies_ranges_from_first_source() {
+        let mut executor = ReplicationExecutor::new(cluster(&[]), Sink::default());
+
+        let mut events = vec![];
+        let report = executor.execute(&"c", &plan(), |p| {
+            events.push((p.hash_range.clone(), p.source.cloned(), p.finished, p.total))
+        });
+
+        assert!(report.is_success());
+        assert_eq!(2, report.items());
+        assert_eq!(
+            vec![(0..=10, Some("a"), 1, 2), (11..=30, Some("b"), 2, 2)],
+            events
+        );
+
+        let (_, sink) = executor.into_inner();
+        assert_eq!(
+            vec![(5, "k5".to_string()), (25, "k25".to_string())],
+            sink.store["c"]
+        );
+    }
+
+    #[test]
+    fn execute_falls_back_to_next_source() {
+        let mut executor = ReplicationExecutor::new(cluster(&[("a", 1)]), Sink::default());
+
+        let report = executor.execute(&"c", &plan(), |_| ());
+
+        assert!(report.is_success());
+        assert_eq!("b", report.transferred[0].source);
+    }
+
+    #[test]
+    fn execute_retries_source_before_fallback() {
+        let mut executor =
+            ReplicationExecutor::with_retries(cluster(&[("a", 2)]), Sink::default(), 2);
+
+        let report = executor.execute(&"c", &plan(), |_| ());
+
+        assert!(report.is_success());
+        assert_eq!("a", report.transferred[0].source);
+    }
+
+    #[test]
+    fn execute_reports_failed_ranges() {
+        let mut executor =
+            ReplicationExecutor::with_retries(cluster(&[("b", 10)]), Sink::default(), 1);
+
+        let report = executor.execute(&"c", &plan(), |_| ());
+
+        assert!(!report.is_success());
+        assert_eq!(1, report.transferred.len());
+        assert_eq!(
+            vec![Failed {
+                target: "c",
+                hash_range: 11..=30,
+                errors: vec![
+                    ("b", TransferError::Source("b is down".to_string())),
+                    ("b", TransferError::Source("b is down".to_string())),
+                ],
+            }],
+            report.failed
+        );
+    }
+
+    #[test]
+    fn replicate_copies_all_missing_keys_after_join() {
+        let mut previous = HashRing::new(0, 10);
+        previous.batch_add(vec!["a", "b"]);
+
+        let mut ring = previous.clone();
+        ring.add("c");
+
+        // store keys on the nodes of the previous ring
+        let mut store: HashMap<&'static str, Vec<(u64, String)>> = HashMap::new();
+        for key in 0..100 {
+            let key = format!("key{key}");
+            let hash = previous.get_hash(&key);
+            for node in previous.get(&key) {
+                store.entry(node).or_default().push((hash, key.clone()));
+            }
+        }
+
+        let source = Cluster {
+            store,
+            down: HashMap::new(),
+        };
+        let mut executor = ReplicationExecutor::new(source, Sink::default());
+
+        let report = executor.replicate(&ring, &previous, &previous.nodes(), |_| ());
+        assert!(report.is_success());
+
+        let (_, sink) = executor.into_inner();
+        let expected = (0..100)
+            .map(|key| format!("key{key}"))
+            .filter(|key| ring.get(key).contains(&"c"))
+            .count();
+
+        assert_eq!(expected, sink.store["c"].len());
+        assert!(!sink.store.contains_key("a"));
+        assert!(!sink.store.contains_key("b"));
+    }
+}
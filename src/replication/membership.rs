@@ -0,0 +1,265 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use super::migration::{insert_range, subtract};
+use crate::hashring::coordinator::Replicas;
+use crate::hashring::strategy::{ReplicationStrategy, SimpleStrategy};
+use crate::hashring::{DefaultHashBuilder, HashRing};
+
+/// change of the cluster emitted by `Membership::update`
+#[derive(Clone, Debug, PartialEq)]
+pub enum MembershipEvent<T> {
+    /// node was added to the HashRing
+    Joined(T),
+    /// node was removed from the HashRing
+    Left(T),
+    /// hash ranges that need to be copied to `target`, as returned by `find_sources`
+    Replicate { target: T, plan: Vec<Replicas<T>> },
+    /// hash ranges `node` stored before, but is no longer responsible for (their keys can be deleted once all
+    /// replication plans are applied)
+    Obsolete {
+        node: T,
+        hash_ranges: Vec<RangeInclusive<u64>>,
+    },
+}
+
+/// Membership keeps a HashRing in line with the desired members of a cluster
+///
+/// Feed the desired member set (e.g. from a config file or service discovery) into `update`. Membership adds and
+/// removes nodes accordingly and emits the resulting events: joined and left nodes, replication plans for all
+/// nodes that need to receive keys and hash ranges that became obsolete. Events are returned and sent to all
+/// subscribers.
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::{HashRing, Membership, MembershipEvent};
+///
+/// let mut ring = HashRing::new(1, 10);
+/// ring.batch_add(vec!["node1", "node2", "node3"]);
+///
+/// let mut membership = Membership::new(ring);
+/// let events = membership.subscribe();
+///
+/// membership.update(vec!["node1", "node2", "node3", "node4"]);
+///
+/// assert_eq!(MembershipEvent::Joined("node4"), events.recv().unwrap());
+/// for event in events.try_iter() {
+///     match event {
+///         MembershipEvent::Replicate { target, plan } => { /* copy plan to target */ }
+///         MembershipEvent::Obsolete { node, hash_ranges } => { /* delete keys later */ }
+///         _ => (),
+///     }
+/// }
+/// ```
+pub struct Membership<T, S = DefaultHashBuilder, R = SimpleStrategy> {
+    ring: HashRing<T, S, R>,
+    subscribers: Vec<Sender<MembershipEvent<T>>>,
+}
+
+impl<T, S, R> Membership<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher + Clone,
+    R: ReplicationStrategy<T> + Clone,
+{
+    /// Create a new `Membership` starting with the members of `ring`
+    pub fn new(ring: HashRing<T, S, R>) -> Self {
+        Membership {
+            ring,
+            subscribers: vec![],
+        }
+    }
+
+    /// returns the current HashRing
+    pub fn hashring(&self) -> &HashRing<T, S, R> {
+        &self.ring
+    }
+
+    /// returns a receiver for all events emitted by future updates
+    pub fn subscribe(&mut self) -> Receiver<MembershipEvent<T>> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+
+        receiver
+    }
+
+    /// add and remove nodes, so the HashRing contains exactly the `desired` nodes, and emit all resulting events
+    ///
+    /// Replication plans use all previous members as source nodes, including nodes that left.
+    /// Returns no events if the members did not change.
+    pub fn update<I>(&mut self, desired: I) -> Vec<MembershipEvent<T>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let previous = self.ring.clone();
+        let current = previous.nodes();
+
+        let mut unique: Vec<T> = vec![];
+        for node in desired {
+            if !unique.contains(&node) {
+                unique.push(node);
+            }
+        }
+
+        let mut events = vec![];
+
+        for node in current.iter().filter(|node| !unique.contains(node)) {
+            self.ring.remove(node);
+            events.push(MembershipEvent::Left(node.clone()));
+        }
+
+        let joined: Vec<T> = unique
+            .into_iter()
+            .filter(|node| !current.contains(node))
+            .collect();
+        for node in joined.iter() {
+            events.push(MembershipEvent::Joined(node.clone()));
+        }
+        self.ring.batch_add(joined);
+
+        if events.is_empty() {
+            return events;
+        }
+
+        let members = self.ring.nodes();
+
+        for target in members.iter().cloned() {
+            let plan = self.ring.find_sources(&target, &previous, &current);
+            if !plan.is_empty() {
+                events.push(MembershipEvent::Replicate { target, plan });
+            }
+        }
+
+        for node in current.iter().filter(|node| members.contains(node)) {
+            let hash_ranges = obsolete(&previous, &self.ring, node);
+            if !hash_ranges.is_empty() {
+                events.push(MembershipEvent::Obsolete {
+                    node: node.clone(),
+                    hash_ranges,
+                });
+            }
+        }
+
+        self.subscribers
+            .retain(|subscriber| events.iter().all(|e| subscriber.send(e.clone()).is_ok()));
+
+        events
+    }
+}
+
+// hash ranges `node` is responsible for in `previous`, but not in `current`
+fn obsolete<T, S, R>(
+    previous: &HashRing<T, S, R>,
+    current: &HashRing<T, S, R>,
+    node: &T,
+) -> Vec<RangeInclusive<u64>>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    let responsible = |ring: &HashRing<T, S, R>| {
+        let mut ranges = vec![];
+        for replicas in ring.get_hash_ranges() {
+            if replicas.nodes.contains(node) {
+                insert_range(&mut ranges, replicas.hash_range);
+            }
+        }
+        ranges
+    };
+
+    let keep = responsible(current);
+
+    responsible(previous)
+        .iter()
+        .flat_map(|range| subtract(range, &keep))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn membership() -> Membership<&'static str> {
+        let mut ring = HashRing::new(1, 10);
+        ring.batch_add(vec!["a", "b", "c", "d"]);
+
+        Membership::new(ring)
+    }
+
+    #[test]
+    fn update_without_changes_emits_nothing() {
+        let mut membership = membership();
+        let events = membership.subscribe();
+
+        assert!(membership.update(vec!["d", "c", "b", "a"]).is_empty());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn update_emits_joined_left_and_plans() {
+        let mut membership = membership();
+        let previous = membership.hashring().clone();
+        let events = membership.subscribe();
+
+        let emitted = membership.update(vec!["a", "b", "c", "e"]);
+
+        assert_eq!(MembershipEvent::Left("d"), emitted[0]);
+        assert_eq!(MembershipEvent::Joined("e"), emitted[1]);
+        assert_eq!(emitted, events.try_iter().collect::<Vec<_>>());
+
+        let mut nodes = membership.hashring().nodes();
+        nodes.sort();
+        assert_eq!(vec!["a", "b", "c", "e"], nodes);
+
+        let plan = emitted
+            .iter()
+            .find_map(|event| match event {
+                MembershipEvent::Replicate { target: "e", plan } => Some(plan.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            membership
+                .hashring()
+                .find_sources(&"e", &previous, &previous.nodes())
+                .len(),
+            plan.len()
+        );
+    }
+
+    #[test]
+    fn obsolete_ranges_are_no_longer_owned() {
+        let mut membership = membership();
+        let emitted = membership.update(vec!["a", "b", "c", "d", "e"]);
+
+        let mut found = false;
+        for event in emitted {
+            let MembershipEvent::Obsolete { node, hash_ranges } = event else {
+                continue;
+            };
+            found = true;
+            assert_ne!("e", node);
+
+            for range in hash_ranges {
+                for hash in [*range.start(), *range.end()] {
+                    assert!(!membership.hashring().get_by_hash(hash).contains(&node));
+                }
+            }
+        }
+        assert!(found, "joining node e takes over ranges of other nodes");
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let mut membership = membership();
+        drop(membership.subscribe());
+
+        membership.update(vec!["a", "b"]);
+        assert!(membership.subscribers.is_empty());
+    }
+}
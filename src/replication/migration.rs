@@ -0,0 +1,400 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "derive")]
+use serde::{Deserialize, Serialize};
+
+use super::executor::ReplicationReport;
+use crate::hashring::HashRing;
+use crate::hashring::coordinator::{Replicas, intersect, width};
+use crate::hashring::strategy::ReplicationStrategy;
+
+/// Migration keeps track of all hash ranges that need to be copied to the nodes of a cluster and which of them are done
+///
+/// Long running migrations (e.g. between two clusters during a deployment) can be interrupted and resumed:
+/// persist the Migration (enable feature `derive` to (de)serialize it), and continue with `remaining_plans`
+/// to only transfer hash ranges (or parts of them) that were not copied yet.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct Migration<T> {
+    transfers: Vec<MigrationTransfer<T>>,
+}
+
+/// hash range that needs to be copied to `target` and the parts of it that are done
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(Serialize, Deserialize))]
+pub struct MigrationTransfer<T> {
+    pub target: T,
+    pub replicas: Replicas<T>,
+    /// sorted, non overlapping sub ranges of `replicas.hash_range` that were copied already
+    pub done: Vec<RangeInclusive<u64>>,
+}
+
+impl<T> Migration<T>
+where
+    T: Clone + PartialEq,
+{
+    /// Create a new `Migration` from replication plans of several target nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `plans` - target nodes and their hash ranges with source nodes, as returned by `find_sources`
+    pub fn new(plans: Vec<(T, Vec<Replicas<T>>)>) -> Self {
+        let transfers = plans
+            .into_iter()
+            .flat_map(|(target, plan)| {
+                plan.into_iter().map(move |replicas| MigrationTransfer {
+                    target: target.clone(),
+                    replicas,
+                    done: vec![],
+                })
+            })
+            .collect();
+
+        Migration { transfers }
+    }
+
+    /// Create a new `Migration` that copies all keys to the nodes of `ring` which are missing compared to `source`
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - HashRing to migrate to, all nodes of this ring are targets
+    /// * `source` - HashRing to migrate from
+    /// * `available_nodes` - define all nodes that can be used for replication in source HashRing
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashring_coordinator::{HashRing, Migration};
+    ///
+    /// let mut ring_original = HashRing::new(0, 10);
+    /// ring_original.batch_add(vec!["node1", "node2"]);
+    ///
+    /// let mut ring_new = HashRing::new(0, 10);
+    /// ring_new.batch_add(vec!["node3", "node4"]);
+    ///
+    /// let mut migration = Migration::plan(&ring_new, &ring_original, &ring_original.nodes());
+    ///
+    /// for (target, plan) in migration.remaining_plans() {
+    ///     for replicas in plan {
+    ///         // copy replicas.hash_range from replicas.nodes to target, then
+    ///         migration.mark_done(&target, &replicas.hash_range);
+    ///     }
+    /// }
+    ///
+    /// assert!(migration.is_complete());
+    /// ```
+    pub fn plan<S, R, S2, R2>(
+        ring: &HashRing<T, S, R>,
+        source: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+    ) -> Self
+    where
+        T: Hash + Debug,
+        S: BuildHasher,
+        R: ReplicationStrategy<T>,
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+    {
+        let plans = ring
+            .nodes()
+            .into_iter()
+            .map(|target| {
+                let plan = ring.find_sources(&target, source, available_nodes);
+                (target, plan)
+            })
+            .collect();
+
+        Migration::new(plans)
+    }
+
+    /// returns all transfers of this migration
+    pub fn transfers(&self) -> &[MigrationTransfer<T>] {
+        &self.transfers
+    }
+
+    /// mark the given hash range (or any part of it) as copied to `target`
+    pub fn mark_done(&mut self, target: &T, hash_range: &RangeInclusive<u64>) {
+        for transfer in self.transfers.iter_mut() {
+            if transfer.target != *target {
+                continue;
+            }
+
+            if let Some(range) = intersect(&transfer.replicas.hash_range, hash_range) {
+                insert_range(&mut transfer.done, range);
+            }
+        }
+    }
+
+    /// mark all hash ranges as done, that were copied according to `report`
+    pub fn apply<E>(&mut self, report: &ReplicationReport<T, E>) {
+        for transferred in report.transferred.iter() {
+            self.mark_done(&transferred.target, &transferred.hash_range);
+        }
+    }
+
+    /// returns the hash ranges (and parts of them) that still need to be copied to `target`
+    pub fn remaining(&self, target: &T) -> Vec<Replicas<T>> {
+        self.transfers
+            .iter()
+            .filter(|transfer| transfer.target == *target)
+            .flat_map(|transfer| transfer.remaining())
+            .collect()
+    }
+
+    /// returns the remaining hash ranges for all targets that are not complete yet
+    pub fn remaining_plans(&self) -> Vec<(T, Vec<Replicas<T>>)> {
+        let mut plans: Vec<(T, Vec<Replicas<T>>)> = vec![];
+
+        for transfer in self.transfers.iter() {
+            let remaining = transfer.remaining();
+            if remaining.is_empty() {
+                continue;
+            }
+
+            match plans
+                .iter_mut()
+                .find(|(target, _)| *target == transfer.target)
+            {
+                Some((_, plan)) => plan.extend(remaining),
+                None => plans.push((transfer.target.clone(), remaining)),
+            }
+        }
+
+        plans
+    }
+
+    /// returns true if `hash` was copied to all targets that need to receive it
+    pub fn is_migrated(&self, hash: u64) -> bool {
+        self.transfers.iter().all(|transfer| {
+            !transfer.replicas.hash_range.contains(&hash)
+                || transfer.done.iter().any(|range| range.contains(&hash))
+        })
+    }
+
+    /// returns true if all hash ranges were copied
+    pub fn is_complete(&self) -> bool {
+        self.transfers
+            .iter()
+            .all(|transfer| transfer.done_width() == width(&transfer.replicas.hash_range))
+    }
+
+    /// returns the share of copied hash space across all transfers, between 0.0 and 1.0
+    pub fn progress(&self) -> f64 {
+        let total: u128 = self
+            .transfers
+            .iter()
+            .map(|transfer| width(&transfer.replicas.hash_range))
+            .sum();
+
+        if total == 0 {
+            return 1.0;
+        }
+
+        let done: u128 = self.transfers.iter().map(|t| t.done_width()).sum();
+
+        done as f64 / total as f64
+    }
+}
+
+impl<T> MigrationTransfer<T>
+where
+    T: Clone,
+{
+    /// returns the parts of this hash range that still need to be copied, with the same source nodes
+    pub fn remaining(&self) -> Vec<Replicas<T>> {
+        subtract(&self.replicas.hash_range, &self.done)
+            .into_iter()
+            .map(|hash_range| Replicas {
+                hash_range,
+                nodes: self.replicas.nodes.clone(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn done_width(&self) -> u128 {
+        self.done.iter().map(width).sum()
+    }
+}
+
+// inserts `range` into the sorted list of non overlapping `ranges`, merging touching and overlapping ranges
+pub(crate) fn insert_range(ranges: &mut Vec<RangeInclusive<u64>>, range: RangeInclusive<u64>) {
+    let mut start = *range.start();
+    let mut end = *range.end();
+
+    ranges.retain(|r| {
+        let touches = *r.start() <= end.saturating_add(1) && start <= r.end().saturating_add(1);
+        if touches {
+            start = start.min(*r.start());
+            end = end.max(*r.end());
+        }
+        !touches
+    });
+
+    let pos = ranges.partition_point(|r| *r.start() < start);
+    ranges.insert(pos, start..=end);
+}
+
+// returns all parts of `range` that are not covered by the sorted, non overlapping `covered` ranges
+pub(crate) fn subtract(
+    range: &RangeInclusive<u64>,
+    covered: &[RangeInclusive<u64>],
+) -> Vec<RangeInclusive<u64>> {
+    let mut remaining = vec![];
+    let mut start = *range.start();
+
+    for c in covered {
+        let Some(c) = intersect(range, c) else {
+            continue;
+        };
+
+        if *c.start() > start {
+            remaining.push(start..=*c.start() - 1);
+        }
+
+        if *c.end() == u64::MAX {
+            return remaining;
+        }
+        start = start.max(*c.end() + 1);
+    }
+
+    if start <= *range.end() {
+        remaining.push(start..=*range.end());
+    }
+
+    remaining
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::executor::Transferred;
+    use pretty_assertions::assert_eq;
+
+    fn migration() -> Migration<&'static str> {
+        Migration::new(vec![
+            (
+                "c",
+                vec![
+                    Replicas {
+                        hash_range: 0..=99,
+                        nodes: vec!["a", "b"],
+                    },
+                    Replicas {
+                        hash_range: 200..=299,
+                        nodes: vec!["b"],
+                    },
+                ],
+            ),
+            (
+                "d",
+                vec![Replicas {
+                    hash_range: 100..=199,
+                    nodes: vec!["a"],
+                }],
+            ),
+        ])
+    }
+
+    #[test]
+    fn remaining_returns_sub_ranges_not_done_yet() {
+        let mut migration = migration();
+
+        migration.mark_done(&"c", &(10..=19));
+        migration.mark_done(&"c", &(20..=29));
+        migration.mark_done(&"c", &(250..=400));
+
+        assert_eq!(
+            vec![
+                Replicas {
+                    hash_range: 0..=9,
+                    nodes: vec!["a", "b"],
+                },
+                Replicas {
+                    hash_range: 30..=99,
+                    nodes: vec!["a", "b"],
+                },
+                Replicas {
+                    hash_range: 200..=249,
+                    nodes: vec!["b"],
+                },
+            ],
+            migration.remaining(&"c")
+        );
+        assert_eq!(
+            vec![10..=29],
+            migration.transfers()[0].done,
+            "touching ranges are merged"
+        );
+
+        assert_eq!(2, migration.remaining_plans().len());
+        assert!(!migration.is_complete());
+
+        assert!(migration.is_migrated(15));
+        assert!(!migration.is_migrated(5));
+        assert!(!migration.is_migrated(150));
+        assert!(migration.is_migrated(1000), "no transfer covers this hash");
+        assert_eq!(70.0 / 300.0, migration.progress());
+    }
+
+    #[test]
+    fn migration_is_complete_once_all_ranges_are_done() {
+        let mut migration = migration();
+
+        migration.mark_done(&"c", &(0..=1000));
+        assert_eq!(
+            vec![("d", migration.remaining(&"d"))],
+            migration.remaining_plans()
+        );
+
+        migration.apply::<()>(&ReplicationReport {
+            transferred: vec![Transferred {
+                target: "d",
+                hash_range: 100..=199,
+                source: "a",
+                items: 3,
+            }],
+            failed: vec![],
+            cancelled: vec![],
+        });
+
+        assert!(migration.is_complete());
+        assert!(migration.remaining_plans().is_empty());
+        assert_eq!(1.0, migration.progress());
+    }
+
+    #[test]
+    fn plan_covers_find_sources_of_all_nodes() {
+        let mut ring_original = HashRing::new(1, 10);
+        ring_original.batch_add(vec!["a", "b", "c"]);
+
+        let mut ring_new = ring_original.clone();
+        ring_new.add("d");
+
+        let migration = Migration::plan(&ring_new, &ring_original, &ring_original.nodes());
+
+        for node in ring_new.nodes() {
+            let mut expected = ring_new.find_sources(&node, &ring_original, &ring_original.nodes());
+            let mut remaining = migration.remaining(&node);
+
+            expected.sort_by_key(|r| *r.hash_range.start());
+            remaining.sort_by_key(|r| *r.hash_range.start());
+
+            assert_eq!(expected, remaining);
+        }
+    }
+
+    #[test]
+    fn subtract_handles_boundaries() {
+        assert_eq!(
+            vec![0..=4, 11..=u64::MAX],
+            subtract(&(0..=u64::MAX), &[5..=10])
+        );
+        assert_eq!(
+            Vec::<RangeInclusive<u64>>::new(),
+            subtract(&(5..=10), &[0..=u64::MAX])
+        );
+        assert_eq!(vec![0..=9], subtract(&(0..=u64::MAX), &[10..=u64::MAX]));
+    }
+}
@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+use super::executor::{Cancelled, Failed, Progress, ReplicationReport, TransferError, Transferred};
+use crate::hashring::coordinator::Replicas;
+use crate::hashring::strategy::ReplicationStrategy;
+use crate::hashring::{DefaultHashBuilder, HashRing};
+
+/// async version of `RangeSource`, reads all keys (items) within a hash range from a node
+pub trait AsyncRangeSource<T> {
+    type Item;
+    type Error;
+
+    /// returns all items stored on `node` whose key hash is within `hash_range`
+    fn fetch(
+        &self,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> impl Future<Output = Result<Vec<Self::Item>, Self::Error>>;
+}
+
+/// async version of `RangeSink`, stores items fetched by an `AsyncRangeSource` on a node
+pub trait AsyncRangeSink<T, Item> {
+    type Error;
+
+    /// store all `items` on `node`
+    fn store(&self, node: &T, items: Vec<Item>) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// limits of `AsyncReplicationExecutor`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// number of hash ranges that are fetched from the same source node at the same time
+    pub per_source: usize,
+    /// number of hash ranges that are stored on the same target node at the same time
+    pub per_target: usize,
+    /// number of retries per source node, before falling back to the next source node
+    pub retries: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            per_source: 1,
+            per_target: 1,
+            retries: 0,
+        }
+    }
+}
+
+/// AsyncReplicationExecutor copies hash ranges from source nodes to target nodes concurrently
+///
+/// All hash ranges are transferred within the calling task, bounded by `Limits` per source and per target node.
+/// Source nodes are tried in the given order (see `find_sources_ranked`), same as for `ReplicationExecutor`.
+/// Use `cancellation_token` to stop a running replication, hash ranges that are not finished yet are reported as cancelled.
+pub struct AsyncReplicationExecutor<Src, Snk> {
+    source: Src,
+    sink: Snk,
+    limits: Limits,
+    cancel: CancellationToken,
+}
+
+enum Outcome<T, E> {
+    Transferred(T, usize),
+    Failed(Vec<(T, E)>),
+    Cancelled,
+}
+
+impl<Src, Snk> AsyncReplicationExecutor<Src, Snk> {
+    /// Create a new `AsyncReplicationExecutor`
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - fetches hash ranges from source nodes
+    /// * `sink` - stores fetched items on target nodes
+    /// * `limits` - concurrency per source and target node and retries
+    pub fn new(source: Src, sink: Snk, limits: Limits) -> Self {
+        AsyncReplicationExecutor {
+            source,
+            sink,
+            limits,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// returns a token to cancel all running and future replications of this executor
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// returns source and sink
+    pub fn into_inner(self) -> (Src, Snk) {
+        (self.source, self.sink)
+    }
+
+    /// copy all hash ranges of all plans to their target
+    ///
+    /// # Arguments
+    ///
+    /// * `plans` - target nodes and their hash ranges with source nodes, as returned by `find_sources`
+    /// * `progress` - called after each hash range is finished, `finished` and `total` count the hash ranges of all plans
+    pub async fn execute<T, F>(
+        &self,
+        plans: &[(T, Vec<Replicas<T>>)],
+        mut progress: F,
+    ) -> ReplicationReport<T, TransferError<Src::Error, Snk::Error>>
+    where
+        T: Clone + PartialEq,
+        Src: AsyncRangeSource<T>,
+        Snk: AsyncRangeSink<T, Src::Item>,
+        F: FnMut(&Progress<T>),
+    {
+        let mut sources: Vec<(T, Semaphore)> = vec![];
+        let mut targets: Vec<(T, Semaphore)> = vec![];
+
+        for (target, plan) in plans {
+            register(&mut targets, target, self.limits.per_target);
+            for node in plan.iter().flat_map(|replicas| replicas.nodes.iter()) {
+                register(&mut sources, node, self.limits.per_source);
+            }
+        }
+
+        let mut jobs = FuturesUnordered::new();
+        for (target, plan) in plans {
+            for replicas in plan {
+                let sources = &sources;
+                let target_permits = semaphore(&targets, target);
+
+                jobs.push(async move {
+                    let outcome = tokio::select! {
+                        biased;
+                        _ = self.cancel.cancelled() => Outcome::Cancelled,
+                        outcome = self.transfer_range(target, target_permits, replicas, sources) => outcome,
+                    };
+
+                    (target, replicas, outcome)
+                });
+            }
+        }
+
+        let total = jobs.len();
+        let mut finished = 0;
+        let mut report = ReplicationReport::default();
+
+        while let Some((target, Replicas { hash_range, .. }, outcome)) = jobs.next().await {
+            finished += 1;
+
+            let (source, items) = match &outcome {
+                Outcome::Transferred(source, items) => (Some(source), *items),
+                _ => (None, 0),
+            };
+
+            progress(&Progress {
+                target,
+                hash_range,
+                source,
+                items,
+                finished,
+                total,
+            });
+
+            match outcome {
+                Outcome::Transferred(source, items) => report.transferred.push(Transferred {
+                    target: target.clone(),
+                    hash_range: hash_range.clone(),
+                    source,
+                    items,
+                }),
+                Outcome::Failed(errors) => report.failed.push(Failed {
+                    target: target.clone(),
+                    hash_range: hash_range.clone(),
+                    errors,
+                }),
+                Outcome::Cancelled => report.cancelled.push(Cancelled {
+                    target: target.clone(),
+                    hash_range: hash_range.clone(),
+                }),
+            }
+        }
+
+        report
+    }
+
+    /// copy all keys that are missing on the nodes of `ring` after a change of the cluster (or a new deployment)
+    ///
+    /// calls `find_sources` for each node of `ring` and executes all plans concurrently
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - current HashRing, all nodes of this ring are targets
+    /// * `previous` - HashRing to find replication nodes in
+    /// * `available_nodes` - define all nodes that can be used for replication in `previous`
+    /// * `progress` - called after each hash range is finished
+    pub async fn replicate<T, S, R, S2, R2, F>(
+        &self,
+        ring: &HashRing<T, S, R>,
+        previous: &HashRing<T, S2, R2>,
+        available_nodes: &[T],
+        progress: F,
+    ) -> ReplicationReport<T, TransferError<Src::Error, Snk::Error>>
+    where
+        T: Hash + Clone + Debug + PartialEq,
+        S: BuildHasher,
+        R: ReplicationStrategy<T>,
+        S2: BuildHasher,
+        R2: ReplicationStrategy<T>,
+        Src: AsyncRangeSource<T>,
+        Snk: AsyncRangeSink<T, Src::Item>,
+        F: FnMut(&Progress<T>),
+    {
+        let plans: Vec<(T, Vec<Replicas<T>>)> = ring
+            .nodes()
+            .into_iter()
+            .map(|target| {
+                let plan = ring.find_sources(&target, previous, available_nodes);
+                (target, plan)
+            })
+            .collect();
+
+        self.execute(&plans, progress).await
+    }
+
+    // tries all source nodes of `replicas` in order until one of them delivers the hash range
+    async fn transfer_range<T>(
+        &self,
+        target: &T,
+        target_permits: &Semaphore,
+        replicas: &Replicas<T>,
+        sources: &[(T, Semaphore)],
+    ) -> Outcome<T, TransferError<Src::Error, Snk::Error>>
+    where
+        T: Clone + PartialEq,
+        Src: AsyncRangeSource<T>,
+        Snk: AsyncRangeSink<T, Src::Item>,
+    {
+        let _target_permit = target_permits.acquire().await;
+        let mut errors = vec![];
+
+        for node in &replicas.nodes {
+            for _ in 0..=self.limits.retries {
+                let result = {
+                    let _source_permit = semaphore(sources, node).acquire().await;
+                    self.transfer(target, node, &replicas.hash_range).await
+                };
+
+                match result {
+                    Ok(items) => return Outcome::Transferred(node.clone(), items),
+                    Err(e) => errors.push((node.clone(), e)),
+                }
+            }
+        }
+
+        Outcome::Failed(errors)
+    }
+
+    async fn transfer<T>(
+        &self,
+        target: &T,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> Result<usize, TransferError<Src::Error, Snk::Error>>
+    where
+        Src: AsyncRangeSource<T>,
+        Snk: AsyncRangeSink<T, Src::Item>,
+    {
+        let items = self
+            .source
+            .fetch(node, hash_range)
+            .await
+            .map_err(TransferError::Source)?;
+        let count = items.len();
+
+        self.sink
+            .store(target, items)
+            .await
+            .map_err(TransferError::Sink)?;
+
+        Ok(count)
+    }
+}
+
+fn register<T: Clone + PartialEq>(semaphores: &mut Vec<(T, Semaphore)>, node: &T, permits: usize) {
+    if !semaphores.iter().any(|(n, _)| n == node) {
+        semaphores.push((node.clone(), Semaphore::new(permits.max(1))));
+    }
+}
+
+fn semaphore<'a, T: PartialEq>(semaphores: &'a [(T, Semaphore)], node: &T) -> &'a Semaphore {
+    semaphores
+        .iter()
+        .find(|(n, _)| n == node)
+        .map(|(_, semaphore)| semaphore)
+        .expect("all nodes of all plans are registered")
+}
+
+/// error of `MemoryTransport`
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemoryTransportError<T> {
+    /// the node was marked as down
+    Down(T),
+}
+
+impl<T: Debug> std::fmt::Display for MemoryTransportError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryTransportError::Down(node) => write!(f, "node {node:?} is down"),
+        }
+    }
+}
+
+impl<T: Debug> std::error::Error for MemoryTransportError<T> {}
+
+/// MemoryTransport keeps key/value pairs of all nodes in memory, to test replication without a network
+///
+/// It serves as `AsyncRangeSource` and `AsyncRangeSink` at the same time and can be shared between executors (clones share all data).
+/// Keys are hashed like `HashRing::get_hash` does, thus both need to use the same hasher.
+#[derive(Clone, Debug)]
+pub struct MemoryTransport<T, K, V, S = DefaultHashBuilder> {
+    hash_builder: S,
+    nodes: Arc<Mutex<Vec<MemoryNode<T, K, V>>>>,
+}
+
+#[derive(Debug)]
+struct MemoryNode<T, K, V> {
+    node: T,
+    store: HashMap<K, V>,
+    down: bool,
+}
+
+impl<T, K, V> MemoryTransport<T, K, V> {
+    /// Create a new `MemoryTransport` for a HashRing using the default hasher
+    pub fn new() -> Self {
+        MemoryTransport::with_hasher(DefaultHashBuilder)
+    }
+}
+
+impl<T, K, V> Default for MemoryTransport<T, K, V> {
+    fn default() -> Self {
+        MemoryTransport::new()
+    }
+}
+
+impl<T, K, V, S> MemoryTransport<T, K, V, S> {
+    /// Create a new `MemoryTransport` for a HashRing using the given hasher
+    pub fn with_hasher(hash_builder: S) -> Self {
+        MemoryTransport {
+            hash_builder,
+            nodes: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl<T, K, V, S> MemoryTransport<T, K, V, S>
+where
+    T: Clone + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    /// store a given key/value pair on `node`
+    pub fn post(&self, node: &T, key: K, value: V) {
+        self.with_node(node, |n| {
+            n.store.insert(key, value);
+        });
+    }
+
+    /// retrieve the value for given key from `node`
+    pub fn get(&self, node: &T, key: &K) -> Option<V> {
+        self.read_node(node, |n| n.store.get(key).cloned())
+            .flatten()
+    }
+
+    /// returns the amount of values stored on `node`
+    pub fn size(&self, node: &T) -> usize {
+        self.read_node(node, |n| n.store.len()).unwrap_or(0)
+    }
+
+    /// a node that is down fails to fetch and store hash ranges
+    pub fn set_down(&self, node: &T, down: bool) {
+        self.with_node(node, |n| n.down = down);
+    }
+
+    // calls `f` with the given node, None if the node has no entry yet
+    fn read_node<F, U>(&self, node: &T, f: F) -> Option<U>
+    where
+        F: FnOnce(&MemoryNode<T, K, V>) -> U,
+    {
+        let nodes = self.nodes.lock().expect("lock is not poisoned");
+
+        nodes.iter().find(|n| n.node == *node).map(f)
+    }
+
+    // calls `f` with the given node, creates an entry for the node if it has none yet
+    fn with_node<F, U>(&self, node: &T, f: F) -> U
+    where
+        F: FnOnce(&mut MemoryNode<T, K, V>) -> U,
+    {
+        let mut nodes = self.nodes.lock().expect("lock is not poisoned");
+
+        let pos = match nodes.iter().position(|n| n.node == *node) {
+            Some(pos) => pos,
+            None => {
+                nodes.push(MemoryNode {
+                    node: node.clone(),
+                    store: HashMap::new(),
+                    down: false,
+                });
+                nodes.len() - 1
+            }
+        };
+
+        f(&mut nodes[pos])
+    }
+}
+
+impl<T, K, V, S> AsyncRangeSource<T> for MemoryTransport<T, K, V, S>
+where
+    T: Clone + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type Error = MemoryTransportError<T>;
+
+    async fn fetch(
+        &self,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> Result<Vec<Self::Item>, Self::Error> {
+        self.read_node(node, |n| {
+            if n.down {
+                return Err(MemoryTransportError::Down(n.node.clone()));
+            }
+
+            Ok(n.store
+                .iter()
+                .filter(|(key, _)| hash_range.contains(&self.hash_builder.hash_one(key)))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect())
+        })
+        .unwrap_or(Ok(vec![]))
+    }
+}
+
+impl<T, K, V, S> AsyncRangeSink<T, (K, V)> for MemoryTransport<T, K, V, S>
+where
+    T: Clone + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    type Error = MemoryTransportError<T>;
+
+    async fn store(&self, node: &T, items: Vec<(K, V)>) -> Result<(), Self::Error> {
+        self.with_node(node, |n| {
+            if n.down {
+                return Err(MemoryTransportError::Down(n.node.clone()));
+            }
+
+            n.store.extend(items);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn cluster() -> (
+        HashRing<&'static str>,
+        HashRing<&'static str>,
+        MemoryTransport<&'static str, String, String>,
+    ) {
+        let mut previous = HashRing::new(1, 10);
+        previous.batch_add(vec!["a", "b", "c"]);
+
+        let transport = MemoryTransport::new();
+        for key in 0..200 {
+            let key = format!("key{key}");
+            for node in previous.get(&key) {
+                transport.post(&node, key.clone(), key.clone());
+            }
+        }
+
+        let mut ring = previous.clone();
+        ring.add("d");
+
+        (previous, ring, transport)
+    }
+
+    #[tokio::test]
+    async fn replicate_copies_all_missing_keys() {
+        let (previous, ring, transport) = cluster();
+
+        let executor =
+            AsyncReplicationExecutor::new(transport.clone(), transport.clone(), Limits::default());
+
+        let mut finished = 0;
+        let report = executor
+            .replicate(&ring, &previous, &previous.nodes(), |p| {
+                finished = p.finished;
+                assert!(p.finished <= p.total);
+            })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(finished, report.transferred.len());
+
+        for key in 0..200 {
+            let key = format!("key{key}");
+            for node in ring.get(&key) {
+                assert_eq!(Some(key.clone()), transport.get(&node, &key));
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_do_not_create_nodes() {
+        let transport: MemoryTransport<&str, String, String> = MemoryTransport::new();
+        transport.post(&"a", "key".to_string(), "value".to_string());
+
+        assert_eq!(None, transport.get(&"b", &"key".to_string()));
+        assert_eq!(0, transport.size(&"b"));
+        assert_eq!(1, transport.size(&"a"));
+        assert_eq!(1, transport.nodes.lock().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn execute_falls_back_if_source_is_down() {
+        let (previous, ring, transport) = cluster();
+        transport.set_down(&"a", true);
+
+        let plan = ring.find_sources(&"d", &previous, &previous.nodes());
+        let executor = AsyncReplicationExecutor::new(
+            transport.clone(),
+            transport.clone(),
+            Limits {
+                per_source: 2,
+                per_target: 2,
+                retries: 1,
+            },
+        );
+
+        let report = executor.execute(&[("d", plan)], |_| ()).await;
+
+        assert!(report.is_success());
+        assert!(report.transferred.iter().all(|t| t.source != "a"));
+    }
+
+    #[tokio::test]
+    async fn execute_reports_cancelled_ranges() {
+        let (previous, ring, transport) = cluster();
+
+        let plan = ring.find_sources(&"d", &previous, &previous.nodes());
+        let executor =
+            AsyncReplicationExecutor::new(transport.clone(), transport.clone(), Limits::default());
+
+        let token = executor.cancellation_token();
+        let report = executor
+            .execute(&[("d", plan.clone())], |p| {
+                if p.finished == 1 {
+                    token.cancel();
+                }
+            })
+            .await;
+
+        assert_eq!(1, report.transferred.len());
+        assert_eq!(plan.len() - 1, report.cancelled.len());
+        assert!(!report.is_success());
+    }
+
+    // counts concurrent fetches, to verify the limits per source
+    struct CountingSource {
+        inner: MemoryTransport<&'static str, String, String>,
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl AsyncRangeSource<&'static str> for CountingSource {
+        type Item = (String, String);
+        type Error = MemoryTransportError<&'static str>;
+
+        async fn fetch(
+            &self,
+            node: &&'static str,
+            hash_range: &RangeInclusive<u64>,
+        ) -> Result<Vec<Self::Item>, Self::Error> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+
+            let items = self.inner.fetch(node, hash_range).await;
+            tokio::task::yield_now().await;
+
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            items
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_bounds_concurrency_per_source() {
+        let (previous, ring, transport) = cluster();
+
+        let plan: Vec<Replicas<&str>> = ring
+            .find_sources(&"d", &previous, &previous.nodes())
+            .into_iter()
+            .map(|replicas| Replicas {
+                hash_range: replicas.hash_range,
+                nodes: vec!["a"],
+            })
+            .collect();
+        assert!(plan.len() > 2);
+
+        let source = CountingSource {
+            inner: transport.clone(),
+            running: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        };
+        let limits = Limits {
+            per_source: 2,
+            per_target: 10,
+            retries: 0,
+        };
+        let executor = AsyncReplicationExecutor::new(source, transport.clone(), limits);
+
+        let report = executor.execute(&[("d", plan)], |_| ()).await;
+        assert!(report.is_success());
+
+        let (source, _) = executor.into_inner();
+        assert_eq!(2, source.peak.load(Ordering::SeqCst));
+    }
+}
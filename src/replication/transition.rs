@@ -0,0 +1,215 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use super::migration::Migration;
+use crate::hashring::strategy::{ReplicationStrategy, SimpleStrategy};
+use crate::hashring::{DefaultHashBuilder, HashRing};
+
+/// TransitionRing routes requests while all keys are migrated from an old to a new cluster (e.g. blue/green deployment)
+///
+/// Writes go to the owners in both clusters. Reads go to the owners in the new cluster once the hash range of
+/// the key was copied to all of them (according to the `Migration`), and to the owners in the old cluster before.
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::{HashRing, TransitionRing};
+///
+/// let mut old = HashRing::new(1, 10);
+/// old.batch_add(vec!["old1", "old2", "old3"]);
+///
+/// let mut new = HashRing::new(1, 10);
+/// new.batch_add(vec!["new1", "new2", "new3"]);
+///
+/// let mut transition = TransitionRing::plan(old.clone(), new.clone());
+///
+/// assert_eq!(old.get(&"foo"), transition.read_targets(&"foo"));
+/// assert_eq!(4, transition.write_targets(&"foo").len());
+///
+/// for (target, plan) in transition.migration().remaining_plans() {
+///     for replicas in plan {
+///         // copy replicas.hash_range from replicas.nodes to target, then
+///         transition.migration_mut().mark_done(&target, &replicas.hash_range);
+///     }
+/// }
+///
+/// assert!(transition.is_complete());
+/// assert_eq!(new.get(&"foo"), transition.read_targets(&"foo"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TransitionRing<T, S = DefaultHashBuilder, R = SimpleStrategy> {
+    old: HashRing<T, S, R>,
+    new: HashRing<T, S, R>,
+    migration: Migration<T>,
+}
+
+impl<T, S, R> TransitionRing<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// Create a new `TransitionRing`
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - HashRing of the cluster to migrate from
+    /// * `new` - HashRing of the cluster to migrate to, both need to use the same hash function
+    /// * `migration` - hash ranges to copy to the new cluster, see `Migration::plan`
+    pub fn new(old: HashRing<T, S, R>, new: HashRing<T, S, R>, migration: Migration<T>) -> Self {
+        TransitionRing {
+            old,
+            new,
+            migration,
+        }
+    }
+
+    /// Create a new `TransitionRing` that copies all keys from all nodes of `old` to `new`
+    pub fn plan(old: HashRing<T, S, R>, new: HashRing<T, S, R>) -> Self {
+        let migration = Migration::plan(&new, &old, &old.nodes());
+
+        TransitionRing::new(old, new, migration)
+    }
+
+    /// returns the HashRing of the cluster to migrate from
+    pub fn old_ring(&self) -> &HashRing<T, S, R> {
+        &self.old
+    }
+
+    /// returns the HashRing of the cluster to migrate to
+    pub fn new_ring(&self) -> &HashRing<T, S, R> {
+        &self.new
+    }
+
+    /// returns the migration between both clusters
+    pub fn migration(&self) -> &Migration<T> {
+        &self.migration
+    }
+
+    /// returns the migration to mark hash ranges as done
+    pub fn migration_mut(&mut self) -> &mut Migration<T> {
+        &mut self.migration
+    }
+
+    /// returns true if the hash range of `key` was copied to all its owners in the new cluster
+    pub fn is_migrated<U: Hash>(&self, key: &U) -> bool {
+        self.migration.is_migrated(self.new.get_hash(key))
+    }
+
+    /// returns true if all hash ranges were copied, the old cluster is not needed anymore
+    pub fn is_complete(&self) -> bool {
+        self.migration.is_complete()
+    }
+
+    /// returns the owners of `key` in both clusters (old owners first), writes need to go to all of them
+    pub fn write_targets<U: Hash>(&self, key: &U) -> Vec<T> {
+        let mut nodes = self.old.get(key);
+
+        for node in self.new.get(key) {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+
+        nodes
+    }
+
+    /// returns the owners of `key` in the new cluster if its hash range was migrated, the old owners otherwise
+    pub fn read_targets<U: Hash>(&self, key: &U) -> Vec<T> {
+        match self.is_migrated(key) {
+            true => self.new.get(key),
+            false => self.old.get(key),
+        }
+    }
+
+    /// returns the HashRing of the new cluster once the migration is complete, or the TransitionRing otherwise
+    pub fn finish(self) -> Result<HashRing<T, S, R>, Self> {
+        match self.is_complete() {
+            true => Ok(self.new),
+            false => Err(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::ops::RangeInclusive;
+
+    fn transition() -> TransitionRing<&'static str> {
+        let mut old = HashRing::new(1, 10);
+        old.batch_add(vec!["a", "b", "c"]);
+
+        let mut new = HashRing::new(1, 10);
+        new.batch_add(vec!["a", "b", "c", "d"]);
+
+        TransitionRing::plan(old, new)
+    }
+
+    #[test]
+    fn write_targets_are_the_union_of_both_rings() {
+        let transition = transition();
+
+        for key in 0..100 {
+            let nodes = transition.write_targets(&key);
+
+            for node in transition.old_ring().get(&key) {
+                assert!(nodes.contains(&node));
+            }
+            for node in transition.new_ring().get(&key) {
+                assert!(nodes.contains(&node));
+            }
+            assert!(nodes.len() <= 4);
+        }
+    }
+
+    // returns a key whose hash is within `range`
+    fn key_in(ring: &HashRing<&'static str>, range: RangeInclusive<u64>) -> u32 {
+        (0..)
+            .find(|key| range.contains(&ring.get_hash(key)))
+            .unwrap()
+    }
+
+    #[test]
+    fn read_targets_follow_migration_progress() {
+        const A: u64 = 1 << 62;
+        const D: u64 = 3 << 61;
+        const B: u64 = 2 << 62;
+        const C: u64 = 3 << 62;
+
+        let old = HashRing::from_tokens(0, vec![("a", A), ("b", B), ("c", C)]).unwrap();
+        let mut new = old.clone();
+        new.add_with_tokens("d", vec![D]).unwrap();
+
+        // d takes over A+1..=D from b, all other hash ranges keep their owner
+        let mut transition = TransitionRing::plan(old.clone(), new);
+        assert!(!transition.is_complete());
+
+        let half = A + (1 << 60);
+        let done = key_in(&old, A + 1..=half);
+        let pending = key_in(&old, half + 1..=D);
+        let unchanged = key_in(&old, B + 1..=C);
+
+        assert_eq!(vec!["b"], transition.read_targets(&done));
+        assert_eq!(vec!["b"], transition.read_targets(&pending));
+        assert_eq!(vec!["c"], transition.read_targets(&unchanged));
+        assert_eq!(vec!["b", "d"], transition.write_targets(&done));
+        assert_eq!(vec!["c"], transition.write_targets(&unchanged));
+
+        transition.migration_mut().mark_done(&"d", &(A + 1..=half));
+
+        assert_eq!(vec!["d"], transition.read_targets(&done));
+        assert_eq!(vec!["b"], transition.read_targets(&pending));
+        assert_eq!(vec!["c"], transition.read_targets(&unchanged));
+
+        let mut transition = transition.finish().unwrap_err();
+        transition.migration_mut().mark_done(&"d", &(half + 1..=D));
+
+        assert!(transition.is_complete());
+        assert_eq!(vec!["d"], transition.read_targets(&done));
+        assert_eq!(vec!["d"], transition.read_targets(&pending));
+        assert_eq!(vec!["c"], transition.read_targets(&unchanged));
+        assert_eq!(vec!["d"], transition.finish().unwrap().get(&pending));
+    }
+}
@@ -0,0 +1,274 @@
+//! deterministic simulation of a cluster to evaluate topologies before rolling them out
+//!
+//! enable feature `simulation` to use this module
+
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use serde::{Deserialize, Serialize};
+
+use crate::hashring::strategy::{ReplicationStrategy, SimpleStrategy, splitmix64};
+use crate::hashring::{DefaultHashBuilder, HashRing};
+use crate::testing::{TestCluster, TestClusterReport};
+
+/// scripted event to replay in a `Simulation`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Event<T> {
+    /// add a new, empty node and copy all its hash ranges from the other nodes
+    Join(T),
+    /// remove a node and copy its hash ranges to the remaining nodes
+    Leave(T),
+    /// the node stays in the HashRing, but does not answer anymore
+    Fail(T),
+    /// a failed node answers again and copies all writes it missed from the other nodes
+    Recover(T),
+    /// write the given amount of random keys
+    Write(usize),
+}
+
+/// result of a single `Event`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Step<T> {
+    pub event: Event<T>,
+    /// amount of keys copied between nodes
+    pub moved: usize,
+    /// amount of hash ranges that could not be copied
+    pub failed_transfers: usize,
+    /// amount of written keys that cannot be read from any node
+    pub unavailable: usize,
+    /// amount of written keys that are missing on at least one responsible node
+    pub under_replicated: usize,
+    /// amount of keys stored per node
+    pub load: Vec<(T, usize)>,
+}
+
+/// result of `Simulation::run`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport<T> {
+    pub seed: u64,
+    pub steps: Vec<Step<T>>,
+}
+
+impl<T: Serialize> SimulationReport<T> {
+    /// returns the report as JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Simulation replays a scripted sequence of events on a `TestCluster`
+///
+/// All keys and values are generated by a seeded random number generator, thus a simulation with the same
+/// HashRing, seed and events always returns the same report.
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::HashRing;
+/// use hashring_coordinator::simulation::{Event, Simulation};
+///
+/// let mut ring = HashRing::new(2, 10);
+/// ring.batch_add(vec!["node1", "node2", "node3"]);
+///
+/// let mut simulation = Simulation::new(ring, 42);
+/// let report = simulation.run(vec![
+///     Event::Write(100),
+///     Event::Join("node4"),
+///     Event::Fail("node1"),
+///     Event::Write(100),
+///     Event::Recover("node1"),
+/// ]);
+///
+/// assert_eq!(0, report.steps[4].under_replicated);
+/// println!("{}", report.to_json().unwrap());
+/// ```
+pub struct Simulation<T, S = DefaultHashBuilder, R = SimpleStrategy> {
+    cluster: TestCluster<T, u64, u64, S, R>,
+    seed: u64,
+    rng: u64,
+    keys: Vec<u64>,
+}
+
+impl<T, S, R> Simulation<T, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    S: BuildHasher + Clone,
+    R: ReplicationStrategy<T> + Clone,
+{
+    /// Create a new `Simulation`
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - initial HashRing of the cluster, all nodes start empty
+    /// * `seed` - seed of the random number generator used to generate keys
+    pub fn new(ring: HashRing<T, S, R>, seed: u64) -> Self {
+        Simulation {
+            cluster: TestCluster::new(ring),
+            seed,
+            rng: seed,
+            keys: vec![],
+        }
+    }
+
+    /// returns the simulated cluster
+    pub fn cluster(&self) -> &TestCluster<T, u64, u64, S, R> {
+        &self.cluster
+    }
+
+    /// replay all events and return the result of each step
+    pub fn run<I>(&mut self, events: I) -> SimulationReport<T>
+    where
+        I: IntoIterator<Item = Event<T>>,
+    {
+        SimulationReport {
+            seed: self.seed,
+            steps: events.into_iter().map(|event| self.step(event)).collect(),
+        }
+    }
+
+    /// replay a single event
+    pub fn step(&mut self, event: Event<T>) -> Step<T> {
+        let report = match &event {
+            Event::Join(node) => {
+                let previous = self.cluster.hashring().clone();
+                self.cluster.add_node(node.clone());
+
+                Some(self.cluster.rebalance(&previous, &self.available()))
+            }
+            Event::Leave(node) => {
+                let previous = self.cluster.hashring().clone();
+                self.cluster.drop_node(node);
+
+                Some(self.cluster.rebalance(&previous, &self.available()))
+            }
+            Event::Fail(node) => {
+                self.cluster.set_down(node, true);
+                None
+            }
+            Event::Recover(node) => {
+                self.cluster.set_down(node, false);
+
+                let mut previous = self.cluster.hashring().clone();
+                previous.remove(node);
+                let available: Vec<T> =
+                    self.available().into_iter().filter(|n| n != node).collect();
+
+                Some(self.cluster.rebalance(&previous, &available))
+            }
+            Event::Write(count) => {
+                for _ in 0..*count {
+                    let key = splitmix64(&mut self.rng);
+                    let value = splitmix64(&mut self.rng);
+
+                    self.cluster.post(key, value);
+                    self.keys.push(key);
+                }
+                None
+            }
+        };
+
+        self.measure(event, report)
+    }
+
+    // all nodes of the cluster that are not down
+    fn available(&self) -> Vec<T> {
+        self.cluster
+            .hashring()
+            .nodes()
+            .into_iter()
+            .filter(|node| !self.cluster.is_down(node))
+            .collect()
+    }
+
+    fn measure(&self, event: Event<T>, report: Option<TestClusterReport<T>>) -> Step<T> {
+        let (moved, failed_transfers) = report
+            .map(|report| (report.items(), report.failed.len()))
+            .unwrap_or_default();
+
+        Step {
+            event,
+            moved,
+            failed_transfers,
+            unavailable: self
+                .keys
+                .iter()
+                .filter(|key| self.cluster.get(key).is_none())
+                .count(),
+            under_replicated: self
+                .keys
+                .iter()
+                .filter(|key| self.cluster.test_get(key).is_err())
+                .count(),
+            load: self.cluster.utilization(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn events() -> Vec<Event<&'static str>> {
+        vec![
+            Event::Write(300),
+            Event::Join("node5"),
+            Event::Fail("node2"),
+            Event::Write(100),
+            Event::Recover("node2"),
+            Event::Leave("node1"),
+        ]
+    }
+
+    fn simulate(seed: u64) -> SimulationReport<&'static str> {
+        let mut ring = HashRing::new(1, 20);
+        ring.batch_add(vec!["node1", "node2", "node3", "node4"]);
+
+        Simulation::new(ring, seed).run(events())
+    }
+
+    #[test]
+    fn simulation_is_deterministic() {
+        assert_eq!(simulate(7), simulate(7));
+        assert_ne!(simulate(7).steps[0].load, simulate(8).steps[0].load);
+    }
+
+    #[test]
+    fn simulation_reports_moved_data_and_gaps() {
+        let report = simulate(7);
+        let steps = &report.steps;
+
+        assert_eq!(6, steps.len());
+
+        assert_eq!(0, steps[0].moved);
+        assert_eq!(
+            600,
+            steps[0].load.iter().map(|(_, size)| size).sum::<usize>()
+        );
+
+        assert!(steps[1].moved > 0, "node5 received data");
+        assert_eq!(0, steps[1].under_replicated);
+
+        assert!(steps[2].under_replicated > 0, "node2 is down");
+        assert_eq!(0, steps[2].unavailable, "all keys have another replica");
+        assert!(steps[3].under_replicated > steps[2].under_replicated);
+
+        assert!(steps[4].moved > 0, "node2 copied missed writes");
+        assert_eq!(0, steps[4].under_replicated);
+
+        assert!(steps[5].moved > 0);
+        assert_eq!(0, steps[5].under_replicated);
+        assert_eq!(4, steps[5].load.len());
+    }
+
+    #[test]
+    fn report_can_be_emitted_as_json() {
+        let report = simulate(7);
+
+        let json = report.to_json().unwrap();
+        let parsed: SimulationReport<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(7, parsed.seed);
+        assert_eq!(Event::Join("node5".to_string()), parsed.steps[1].event);
+    }
+}
@@ -0,0 +1,455 @@
+//! in-memory cluster to test rebalancing and replication logic built on `HashRing` without real nodes
+//!
+//! enable feature `testing` to use this module
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeInclusive;
+
+use crate::hashring::strategy::{ReplicationStrategy, SimpleStrategy};
+use crate::hashring::{DefaultHashBuilder, HashRing};
+use crate::replication::executor::{
+    RangeSink, RangeSource, ReplicationExecutor, ReplicationReport, TransferError,
+};
+
+/// error of a `TestCluster` node
+#[derive(Clone, Debug, PartialEq)]
+pub enum TestClusterError<T> {
+    /// the node is not part of the cluster
+    UnknownNode(T),
+    /// the node was marked as down
+    Down(T),
+}
+
+impl<T: Debug> Display for TestClusterError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestClusterError::UnknownNode(node) => write!(f, "node {node:?} is unknown"),
+            TestClusterError::Down(node) => write!(f, "node {node:?} is down"),
+        }
+    }
+}
+
+impl<T: Debug> std::error::Error for TestClusterError<T> {}
+
+/// report of `TestCluster::rebalance` and `TestCluster::synchronize`
+pub type TestClusterReport<T> =
+    ReplicationReport<T, TransferError<TestClusterError<T>, TestClusterError<T>>>;
+
+// a node of the cluster storing key/value pairs
+#[derive(Clone, Debug)]
+struct Store<T, K, V> {
+    node: T,
+    values: HashMap<K, V>,
+    down: bool,
+}
+
+/// TestCluster keeps track of all nodes in a cluster and stores their key/value pairs in memory
+///
+/// It redirects `post` and `get` calls to the nodes responsible for a given key (as defined by the HashRing)
+/// and replicates entries across nodes with `rebalance` and `synchronize`, using `ReplicationExecutor`.
+///
+/// # Examples
+///
+/// ```
+/// use hashring_coordinator::HashRing;
+/// use hashring_coordinator::testing::TestCluster;
+///
+/// let mut ring = HashRing::new(1, 10);
+/// ring.batch_add(vec!["node1", "node2", "node3"]);
+///
+/// let mut cluster: TestCluster<&str, String, u32> = TestCluster::new(ring);
+/// cluster.post("foo".to_string(), 42);
+///
+/// let previous = cluster.hashring().clone();
+/// cluster.add_node("node4");
+///
+/// let report = cluster.rebalance(&previous, &previous.nodes());
+///
+/// assert!(report.is_success());
+/// assert_eq!(Ok(()), cluster.test_get(&"foo".to_string()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TestCluster<T, K, V, S = DefaultHashBuilder, R = SimpleStrategy> {
+    ring: HashRing<T, S, R>,
+    stores: Vec<Store<T, K, V>>,
+}
+
+impl<T, K, V, S, R> TestCluster<T, K, V, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    /// create a new cluster with an empty node for each node of `ring`
+    pub fn new(ring: HashRing<T, S, R>) -> Self {
+        let stores = ring
+            .nodes()
+            .into_iter()
+            .map(|node| Store {
+                node,
+                values: HashMap::new(),
+                down: false,
+            })
+            .collect();
+
+        TestCluster { ring, stores }
+    }
+
+    /// returns the current HashRing of the cluster
+    pub fn hashring(&self) -> &HashRing<T, S, R> {
+        &self.ring
+    }
+
+    /// add a new, empty node to the cluster
+    pub fn add_node(&mut self, node: T) {
+        if self.store(&node).is_none() {
+            self.stores.push(Store {
+                node: node.clone(),
+                values: HashMap::new(),
+                down: false,
+            });
+        }
+        self.ring.add(node);
+    }
+
+    /// remove a node from the cluster, all its values are lost
+    pub fn drop_node(&mut self, node: &T) {
+        self.stores.retain(|store| store.node != *node);
+        self.ring.remove(node);
+    }
+
+    /// a node that is down stays in the HashRing, but does not store or deliver any values
+    pub fn set_down(&mut self, node: &T, down: bool) {
+        if let Some(store) = self.stores.iter_mut().find(|store| store.node == *node) {
+            store.down = down;
+        }
+    }
+
+    /// returns true if the node was marked as down
+    pub fn is_down(&self, node: &T) -> bool {
+        self.store(node).is_some_and(|store| store.down)
+    }
+
+    /// store a given key/value pair on all nodes responsible for `key`
+    ///
+    /// returns the number of nodes that stored the value (nodes that are down are skipped)
+    pub fn post(&mut self, key: K, value: V) -> usize {
+        let mut stored = 0;
+
+        for node in self.ring.get(&key) {
+            if let Some(store) = self.stores.iter_mut().find(|store| store.node == node)
+                && !store.down
+            {
+                store.values.insert(key.clone(), value.clone());
+                stored += 1;
+            }
+        }
+
+        stored
+    }
+
+    /// retrieve the value for given key from the first responsible node that is not down
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.ring
+            .get(key)
+            .iter()
+            .filter_map(|node| self.store(node))
+            .filter(|store| !store.down)
+            .find_map(|store| store.values.get(key))
+    }
+
+    /// retrieve the value for given key from `node`, regardless of the nodes responsible for `key`
+    pub fn get_from(&self, node: &T, key: &K) -> Option<&V> {
+        self.store(node).and_then(|store| store.values.get(key))
+    }
+
+    /// tests to retrieve the value for `key` from all nodes that should contain the key
+    ///
+    /// returns Ok if all nodes returned the same value, or Err(usize) with the amount of nodes that did not return
+    /// the expected value (nodes that are down count as mismatch)
+    pub fn test_get(&self, key: &K) -> Result<(), usize>
+    where
+        V: PartialEq,
+    {
+        let mut value = None;
+        let mut mismatch = 0;
+
+        for node in self.ring.get(key) {
+            let val = self
+                .store(&node)
+                .filter(|store| !store.down)
+                .and_then(|store| store.values.get(key));
+
+            match val {
+                Some(val) if value.is_none() => value = Some(val),
+                Some(val) if value == Some(val) => (),
+                _ => mismatch += 1,
+            }
+        }
+
+        match mismatch {
+            0 => Ok(()),
+            _ => Err(mismatch),
+        }
+    }
+
+    /// returns for each node how many values are currently stored
+    pub fn utilization(&self) -> Vec<(T, usize)> {
+        self.stores
+            .iter()
+            .map(|store| (store.node.clone(), store.values.len()))
+            .collect()
+    }
+
+    /// synchronize entries inside this cluster
+    /// based on the changes / difference to the provided (previous) HashRing
+    pub fn rebalance(
+        &mut self,
+        previous: &HashRing<T, S, R>,
+        available_nodes: &[T],
+    ) -> TestClusterReport<T> {
+        let (report, incoming) = replicate(
+            &self.ring,
+            &self.stores,
+            &self.stores,
+            previous,
+            available_nodes,
+        );
+        self.receive(incoming);
+
+        report
+    }
+
+    /// synchronize all entries from another cluster into this cluster
+    pub fn synchronize(&mut self, from: &TestCluster<T, K, V, S, R>) -> TestClusterReport<T> {
+        let (report, incoming) = replicate(
+            &self.ring,
+            &self.stores,
+            &from.stores,
+            &from.ring,
+            &from.ring.nodes(),
+        );
+        self.receive(incoming);
+
+        report
+    }
+
+    fn store(&self, node: &T) -> Option<&Store<T, K, V>> {
+        self.stores.iter().find(|store| store.node == *node)
+    }
+
+    fn receive(&mut self, incoming: Vec<(T, Vec<(K, V)>)>) {
+        for (node, values) in incoming {
+            if let Some(store) = self.stores.iter_mut().find(|store| store.node == node) {
+                store.values.extend(values);
+            }
+        }
+    }
+}
+
+// copies all missing hash ranges from `sources` to the nodes of `ring`, values are collected and stored by the caller
+#[allow(clippy::type_complexity)]
+fn replicate<T, K, V, S, R>(
+    ring: &HashRing<T, S, R>,
+    targets: &[Store<T, K, V>],
+    sources: &[Store<T, K, V>],
+    previous: &HashRing<T, S, R>,
+    available_nodes: &[T],
+) -> (TestClusterReport<T>, Vec<(T, Vec<(K, V)>)>)
+where
+    T: Hash + Clone + Debug + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    let source = StoreSource {
+        ring,
+        stores: sources,
+    };
+    let sink = StoreSink {
+        stores: targets,
+        incoming: vec![],
+    };
+
+    let mut executor = ReplicationExecutor::new(source, sink);
+    let report = executor.replicate(ring, previous, available_nodes, |_| ());
+    let (_, sink) = executor.into_inner();
+
+    (report, sink.incoming)
+}
+
+struct StoreSource<'a, T, K, V, S, R> {
+    ring: &'a HashRing<T, S, R>,
+    stores: &'a [Store<T, K, V>],
+}
+
+impl<T, K, V, S, R> RangeSource<T> for StoreSource<'_, T, K, V, S, R>
+where
+    T: Hash + Clone + Debug + PartialEq,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+    R: ReplicationStrategy<T>,
+{
+    type Item = (K, V);
+    type Error = TestClusterError<T>;
+
+    fn fetch(
+        &mut self,
+        node: &T,
+        hash_range: &RangeInclusive<u64>,
+    ) -> Result<Vec<Self::Item>, Self::Error> {
+        let store = available(self.stores, node)?;
+
+        Ok(store
+            .values
+            .iter()
+            .filter(|(key, _)| hash_range.contains(&self.ring.get_hash(key)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+}
+
+struct StoreSink<'a, T, K, V> {
+    stores: &'a [Store<T, K, V>],
+    incoming: Vec<(T, Vec<(K, V)>)>,
+}
+
+impl<T, K, V> RangeSink<T, (K, V)> for StoreSink<'_, T, K, V>
+where
+    T: Clone + PartialEq,
+{
+    type Error = TestClusterError<T>;
+
+    fn store(&mut self, node: &T, items: Vec<(K, V)>) -> Result<(), Self::Error> {
+        available(self.stores, node)?;
+        self.incoming.push((node.clone(), items));
+
+        Ok(())
+    }
+}
+
+fn available<'a, T, K, V>(
+    stores: &'a [Store<T, K, V>],
+    node: &T,
+) -> Result<&'a Store<T, K, V>, TestClusterError<T>>
+where
+    T: Clone + PartialEq,
+{
+    match stores.iter().find(|store| store.node == *node) {
+        None => Err(TestClusterError::UnknownNode(node.clone())),
+        Some(store) if store.down => Err(TestClusterError::Down(node.clone())),
+        Some(store) => Ok(store),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn cluster() -> (TestCluster<&'static str, String, String>, Vec<String>) {
+        let mut ring = HashRing::new(2, 20);
+        ring.batch_add(vec!["node1", "node2", "node3", "node4", "node5"]);
+
+        let mut cluster = TestCluster::new(ring);
+        let mut keys = vec![];
+
+        for i in 0..500 {
+            let key = format!("key_{i}");
+            assert_eq!(3, cluster.post(key.clone(), format!("value_{i}")));
+            keys.push(key);
+        }
+
+        (cluster, keys)
+    }
+
+    fn assert_all_keys_found(cluster: &TestCluster<&'static str, String, String>, keys: &[String]) {
+        for key in keys {
+            assert_eq!(Ok(()), cluster.test_get(key), "{key} not found");
+        }
+    }
+
+    #[test]
+    fn rebalance_after_node_joined() {
+        let (mut cluster, keys) = cluster();
+
+        let previous = cluster.hashring().clone();
+        cluster.add_node("node6");
+
+        assert!(keys.iter().any(|key| cluster.test_get(key).is_err()));
+
+        let report = cluster.rebalance(&previous, &previous.nodes());
+
+        assert!(report.is_success());
+        assert_all_keys_found(&cluster, &keys);
+        assert!(
+            cluster
+                .utilization()
+                .iter()
+                .any(|(node, size)| *node == "node6" && *size > 0)
+        );
+    }
+
+    #[test]
+    fn rebalance_after_node_left() {
+        let (mut cluster, keys) = cluster();
+
+        let previous = cluster.hashring().clone();
+        cluster.drop_node(&"node3");
+
+        let available = cluster.hashring().nodes();
+        let report = cluster.rebalance(&previous, &available);
+
+        assert!(report.is_success());
+        assert_all_keys_found(&cluster, &keys);
+        assert_eq!(4, cluster.utilization().len());
+    }
+
+    #[test]
+    fn rebalance_falls_back_if_source_is_down() {
+        let (mut cluster, keys) = cluster();
+
+        let previous = cluster.hashring().clone();
+        cluster.add_node("node6");
+        cluster.set_down(&"node1", true);
+
+        let report = cluster.rebalance(&previous, &previous.nodes());
+        assert!(report.is_success());
+
+        cluster.set_down(&"node1", false);
+        assert_all_keys_found(&cluster, &keys);
+    }
+
+    #[test]
+    fn nodes_that_are_down_do_not_answer() {
+        let (mut cluster, keys) = cluster();
+
+        let key = &keys[0];
+        let node = cluster.hashring().get(key)[0];
+        cluster.set_down(&node, true);
+
+        assert!(cluster.is_down(&node));
+        assert_eq!(Err(1), cluster.test_get(key));
+        assert_eq!(Some(&"value_0".to_string()), cluster.get(key));
+    }
+
+    #[test]
+    fn synchronize_into_new_cluster() {
+        let (cluster, keys) = cluster();
+
+        let mut ring = HashRing::new(2, 20);
+        ring.batch_add(vec!["node11", "node12", "node13", "node14"]);
+
+        let mut replacement = TestCluster::new(ring);
+        let report = replacement.synchronize(&cluster);
+
+        assert!(report.is_success());
+        assert_all_keys_found(&replacement, &keys);
+    }
+}
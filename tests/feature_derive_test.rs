@@ -1,7 +1,8 @@
 #[cfg(feature = "derive")]
 #[cfg(test)]
 mod tests {
-    use hashring_coordinator::Replicas;
+    use hashring_coordinator::gossip::{Member, MemberState, Message};
+    use hashring_coordinator::{HashRing, Migration, Replicas};
 
     #[test]
     fn test_serialize_and_deserialize_replicas() {
@@ -20,4 +21,63 @@ mod tests {
         // Assert that the original and deserialized instances are equal
         assert_eq!(original, deserialized);
     }
+
+    #[test]
+    fn test_resume_migration_after_serialization() {
+        let mut ring_original = HashRing::new(1, 10);
+        ring_original.batch_add(vec!["node1", "node2", "node3"]);
+
+        let mut ring_new = HashRing::new(1, 10);
+        ring_new.batch_add(vec!["node4", "node5"]);
+
+        let mut migration = Migration::plan(&ring_new, &ring_original, &ring_original.nodes());
+
+        // copy the first hash range of each target, then get interrupted
+        for (target, plan) in migration.remaining_plans() {
+            migration.mark_done(&target, &plan[0].hash_range);
+        }
+
+        let serialized = serde_json::to_string(&migration).expect("Serialization failed");
+        let mut resumed: Migration<&str> =
+            serde_json::from_str(&serialized).expect("Deserialization failed");
+
+        assert_eq!(migration, resumed);
+        assert!(!resumed.is_complete());
+
+        for (target, plan) in resumed.remaining_plans() {
+            for replicas in plan {
+                resumed.mark_done(&target, &replicas.hash_range);
+            }
+        }
+
+        assert!(resumed.is_complete());
+    }
+
+    #[test]
+    fn test_serialize_and_deserialize_gossip_messages() {
+        let original = Message::PingReq {
+            from: "node1".to_string(),
+            target: "node2".to_string(),
+            seq: 42,
+            updates: vec![
+                Member {
+                    node: "node2".to_string(),
+                    state: MemberState::Suspect,
+                    incarnation: 3,
+                },
+                Member {
+                    node: "node3".to_string(),
+                    state: MemberState::Dead,
+                    incarnation: 1,
+                },
+            ],
+        };
+
+        // a network transport sends the serialized message to the peer
+        let serialized = serde_json::to_vec(&original).expect("Serialization failed");
+        let deserialized: Message<String> =
+            serde_json::from_slice(&serialized).expect("Deserialization failed");
+
+        assert_eq!(original, deserialized);
+    }
 }